@@ -0,0 +1,127 @@
+use std::time::Duration;
+use crate::error::MaestroError;
+use crate::maestro::{Connection, Maestro};
+
+pub(crate) const DEFAULT_BAUD_RATE: u32 = 9600;
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_millis(10);
+pub(crate) const DEFAULT_CHANNEL_COUNT: u8 = 12;
+pub(crate) const MAX_CHANNEL_COUNT: u8 = 24;
+
+/// Collects the connection settings for a `Maestro` before the serial port is opened.
+///
+/// # Example:
+/// ```
+/// use std::time::Duration;
+/// use maestro_control::MaestroBuilder;
+///
+/// let maestro = MaestroBuilder::new()
+///     .port("COM1")
+///     .channel_count(24)
+///     .baud(115200)
+///     .timeout(Duration::from_millis(50))
+///     .device_number(12)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaestroBuilder {
+    port: Option<String>,
+    baud: u32,
+    timeout: Duration,
+    device_number: Option<u8>,
+    channel_count: u8
+}
+
+impl Default for MaestroBuilder {
+    fn default() -> Self {
+        MaestroBuilder {
+            port: None,
+            baud: DEFAULT_BAUD_RATE,
+            timeout: DEFAULT_TIMEOUT,
+            device_number: None,
+            channel_count: DEFAULT_CHANNEL_COUNT,
+        }
+    }
+}
+
+impl MaestroBuilder {
+    /// Creates a builder with the default settings: 9600 baud, a 10ms timeout, 12 channels and the Compact protocol.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the serial port to open. This is required.
+    pub fn port(mut self, port: &str) -> Self {
+        self.port = Some(port.to_string());
+        self
+    }
+
+    /// Sets the baud rate. It should match the rate configured in the Maestro Control Center (up to 200000).
+    pub fn baud(mut self, baud: u32) -> Self {
+        self.baud = baud;
+        self
+    }
+
+    /// Sets how long reads and writes wait before failing. See [`Maestro::set_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Addresses the Maestro with the Pololu protocol using the given device number.
+    ///
+    /// Use this when several Maestros share a serial line. Every command is prefixed with `0xAA` and
+    /// `device_number`, so only the board configured with that device number responds.
+    pub fn device_number(mut self, device_number: u8) -> Self {
+        self.device_number = Some(device_number);
+        self
+    }
+
+    /// Sets the number of channels on the board (6, 12, 18 or 24 for the Maestro family).
+    pub fn channel_count(mut self, channel_count: u8) -> Self {
+        self.channel_count = channel_count;
+        self
+    }
+
+    /// Opens the serial port and creates the `Maestro`.
+    ///
+    /// Ports are opened in exclusive mode and are not released until the `Maestro` instance is dropped.
+    /// # Errors
+    /// - `OutOfBounds` if the device number is greater than 127 or the channel count is not 1 <= x <= 24
+    /// - `UnableToConnect` if no port was set
+    /// - `Serial` if the serial port could not be opened
+    pub fn build(self) -> Result<Maestro, MaestroError> {
+        if let Some(device_number) = self.device_number {
+            if device_number > 0x7F { return Err(MaestroError::OutOfBounds) }
+        }
+        if self.channel_count == 0 || self.channel_count > MAX_CHANNEL_COUNT { return Err(MaestroError::OutOfBounds) }
+        let port = self.port.ok_or(MaestroError::UnableToConnect)?;
+        let serial_port = serialport::new(&port, self.baud).timeout(self.timeout).open()?;
+        let mut maestro = Maestro::from_transport(serial_port);
+        maestro.set_connection(Connection { port, baud: self.baud });
+        maestro.device_number = self.device_number;
+        maestro.channel_count = self.channel_count;
+        Ok(maestro)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_without_port_fails() {
+        assert!(matches!(MaestroBuilder::new().build(), Err(MaestroError::UnableToConnect)));
+    }
+
+    #[test]
+    fn build_rejects_invalid_device_number() {
+        let res = MaestroBuilder::new().port("COM0").device_number(128).build();
+        assert!(matches!(res, Err(MaestroError::OutOfBounds)));
+    }
+
+    #[test]
+    fn build_rejects_invalid_channel_count() {
+        let res = MaestroBuilder::new().port("COM0").channel_count(25).build();
+        assert!(matches!(res, Err(MaestroError::OutOfBounds)));
+    }
+}
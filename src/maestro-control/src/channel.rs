@@ -0,0 +1,72 @@
+use std::fmt;
+use crate::builder::MAX_CHANNEL_COUNT;
+use crate::error::MaestroError;
+
+/// A validated Maestro channel number.
+///
+/// `Channel::new` only checks against the largest Maestro (24 channels). Use [`crate::Maestro::channel`]
+/// to check against the channel count of a specific board.
+///
+/// # Example:
+/// ```
+/// use maestro_control::Channel;
+///
+/// let channel = Channel::new(5).unwrap();
+/// assert_eq!(channel.index(), 5);
+/// assert!(Channel::new(24).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Channel(u8);
+
+impl Channel {
+    /// Creates a channel.
+    ///
+    /// `index` should be below 24.
+    /// # Errors:
+    /// - `InvalidChannel` if index is out of range
+    pub fn new(index: u8) -> Result<Self, MaestroError> {
+        Self::with_channel_count(index, MAX_CHANNEL_COUNT)
+    }
+
+    pub(crate) fn with_channel_count(index: u8, channel_count: u8) -> Result<Self, MaestroError> {
+        if index >= channel_count { return Err(MaestroError::InvalidChannel) }
+        Ok(Channel(index))
+    }
+
+    /// The channel number.
+    pub fn index(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<Channel> for u8 {
+    fn from(channel: Channel) -> u8 {
+        channel.0
+    }
+}
+
+impl TryFrom<u8> for Channel {
+    type Error = MaestroError;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        Channel::new(index)
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_rejects_channels_past_largest_board() {
+        assert_eq!(Channel::new(23).unwrap().index(), 23);
+        assert!(matches!(Channel::new(24), Err(MaestroError::InvalidChannel)));
+        assert!(matches!(Channel::try_from(200), Err(MaestroError::InvalidChannel)));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::error::MaestroError;
+
+/// Target, in quarter-microseconds, for a servo at 0 degrees (a 496µs pulse).
+pub(crate) const ZERO_DEGREES_QUARTER_US: f64 = 1984.0;
+/// Quarter-microseconds per degree, spreading 0 to 180 degrees over a 2000µs pulse range.
+pub(crate) const QUARTER_US_PER_DEGREE: f64 = 8000.0 / 180.0;
+
+/// Converts an angle in degrees into a Maestro target in quarter-microseconds.
+///
+/// Assumes a servo that sweeps 180 degrees over pulses from 496µs (0 degrees) to 2496µs (180 degrees).
+/// This is the conversion used by `Maestro::set_position`.
+///
+/// `deg` should be a degree 0 <= x <= 180
+///
+/// # Example:
+/// ```
+/// use maestro_control::degrees_to_quarter_us;
+///
+/// assert_eq!(degrees_to_quarter_us(90.0).unwrap(), 5984);
+/// ```
+/// # Errors:
+/// - `OutOfBounds` if deg is outside 0 to 180 degrees
+pub fn degrees_to_quarter_us(deg: f64) -> Result<u16, MaestroError> {
+    if !(0.0..=180.0).contains(&deg) { return Err(MaestroError::OutOfBounds) }
+    Ok((ZERO_DEGREES_QUARTER_US + deg * QUARTER_US_PER_DEGREE).round() as u16)
+}
+
+/// Converts a Maestro target in quarter-microseconds into an angle in degrees.
+///
+/// This is the exact inverse of [`degrees_to_quarter_us`] and uses the same servo range. Targets
+/// outside 1984 to 9984 map to angles outside 0 to 180 degrees.
+///
+/// # Example:
+/// ```
+/// use maestro_control::quarter_us_to_degrees;
+///
+/// assert_eq!(quarter_us_to_degrees(5984), 90.0);
+/// ```
+pub fn quarter_us_to_degrees(raw: u16) -> f64 {
+    (raw as f64 - ZERO_DEGREES_QUARTER_US) / QUARTER_US_PER_DEGREE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degrees_to_quarter_us_range() {
+        assert_eq!(degrees_to_quarter_us(0.0).unwrap(), 1984);
+        assert_eq!(degrees_to_quarter_us(90.0).unwrap(), 5984);
+        assert_eq!(degrees_to_quarter_us(180.0).unwrap(), 9984);
+    }
+
+    #[test]
+    fn degrees_to_quarter_us_out_of_bounds() {
+        assert!(matches!(degrees_to_quarter_us(-0.1), Err(MaestroError::OutOfBounds)));
+        assert!(matches!(degrees_to_quarter_us(180.1), Err(MaestroError::OutOfBounds)));
+        assert!(matches!(degrees_to_quarter_us(f64::NAN), Err(MaestroError::OutOfBounds)));
+    }
+
+    #[test]
+    fn quarter_us_to_degrees_range() {
+        assert_eq!(quarter_us_to_degrees(1984), 0.0);
+        assert_eq!(quarter_us_to_degrees(9984), 180.0);
+    }
+
+    #[test]
+    fn degrees_round_trip() {
+        for tenth in 0..=1800 {
+            let deg = tenth as f64 / 10.0;
+            let round_trip = quarter_us_to_degrees(degrees_to_quarter_us(deg).unwrap());
+            assert!((round_trip - deg).abs() < 0.1, "{} round-tripped to {}", deg, round_trip);
+        }
+    }
+}
@@ -1,17 +1,147 @@
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+/// Errors returned when communicating with a Maestro.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum MaestroError {
+    /// The serial port could not be opened or configured.
     #[error("Unable to connect to Maestro!")]
     UnableToConnect,
+    /// A command could not be written to the serial port.
     #[error("Lost connection to Maestro!")]
     UnableToSend,
-    #[error("Invalid channel parameter passed! Valid parameters are 0-11")]
+    /// The serial port is gone, typically because the Maestro was unplugged.
+    ///
+    /// Unlike `UnableToSend`, retrying will not help until the port is reopened.
+    #[error("Maestro is disconnected")]
+    Disconnected,
+    /// A channel outside the board's range was passed.
+    #[error("Invalid channel parameter passed! Channel must be below the board's channel count")]
     InvalidChannel,
+    /// The Maestro did not send back a complete response.
     #[error("Unable to receive date!")]
     UnableToReceive,
+    /// The Maestro answered Get Moving State with something other than 0 or 1.
     #[error("Invalid moving state received from Maestro. Value should be 0 or 1")]
     InvalidMovingState,
+    /// An input value was outside its valid range.
     #[error("Input out of bounds")]
-    OutOfBounds
-}
\ No newline at end of file
+    OutOfBounds,
+    /// The channel has no target, so its output is off and it has no position.
+    #[error("Channel is disabled")]
+    ChannelDisabled,
+    /// The system's serial ports could not be listed.
+    #[error("Unable to list serial ports")]
+    PortEnumerationFailed,
+    /// A list of channels and a list of values had different lengths.
+    #[error("Number of channels does not match number of values")]
+    LengthMismatch,
+    /// The servos did not stop moving before the timeout elapsed.
+    #[error("Timed out waiting for servos to stop moving")]
+    MoveTimeout,
+    /// A target was outside the limits configured for its channel.
+    #[error("Target exceeds the channel's configured limits")]
+    LimitExceeded,
+    /// The Maestro answered Get Script Status with something other than 0 or 1.
+    #[error("Invalid script status received from Maestro. Value should be 0 or 1")]
+    InvalidScriptStatus,
+    /// No home position was set for the channel.
+    #[error("No home position set for channel")]
+    HomeNotSet,
+    /// The serial port reported an error, such as the device not existing or access being denied.
+    #[error("Serial port error: {0}")]
+    Serial(String),
+    /// The channel did not reach its home position.
+    #[error("Channel {0} did not reach its home position")]
+    HomeVerificationFailed(u8)
+}
+
+impl From<serialport::Error> for MaestroError {
+    fn from(error: serialport::Error) -> Self {
+        MaestroError::Serial(error.to_string())
+    }
+}
+
+/// Error flags reported by the Maestro through the Get Errors command.
+///
+/// Each method reports whether the corresponding bit was set. Use `raw` for the full bitfield.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaestroErrorFlags {
+    raw: u16
+}
+
+impl MaestroErrorFlags {
+    /// Wraps a raw error bitfield as returned by the Maestro.
+    pub fn from_raw(raw: u16) -> Self {
+        MaestroErrorFlags { raw }
+    }
+
+    /// The raw error bitfield.
+    pub fn raw(&self) -> u16 {
+        self.raw
+    }
+
+    /// Returns true if no error flags are set.
+    pub fn is_empty(&self) -> bool {
+        self.raw == 0
+    }
+
+    /// A hardware-level error on the serial line, usually caused by a baud rate mismatch.
+    pub fn serial_signal_error(&self) -> bool {
+        self.bit(0)
+    }
+
+    /// The serial receive hardware was overrun by incoming bytes.
+    pub fn serial_overrun(&self) -> bool {
+        self.bit(1)
+    }
+
+    /// The Maestro's serial receive buffer filled up.
+    pub fn serial_buffer_full(&self) -> bool {
+        self.bit(2)
+    }
+
+    /// A command was received with an incorrect CRC byte.
+    pub fn serial_crc_error(&self) -> bool {
+        self.bit(3)
+    }
+
+    /// A malformed command was received.
+    pub fn serial_protocol_error(&self) -> bool {
+        self.bit(4)
+    }
+
+    /// The serial timeout configured on the board elapsed.
+    pub fn serial_timeout(&self) -> bool {
+        self.bit(5)
+    }
+
+    /// The script's data stack overflowed or underflowed.
+    pub fn script_stack_error(&self) -> bool {
+        self.bit(6)
+    }
+
+    /// The script's call stack overflowed or underflowed.
+    pub fn script_call_stack_error(&self) -> bool {
+        self.bit(7)
+    }
+
+    /// The script's program counter went out of bounds.
+    pub fn script_program_counter_error(&self) -> bool {
+        self.bit(8)
+    }
+
+    fn bit(&self, bit: u16) -> bool {
+        self.raw & (1 << bit) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialport_error_keeps_description() {
+        let error = serialport::Error::new(serialport::ErrorKind::NoDevice, "no such device");
+        assert_eq!(MaestroError::from(error), MaestroError::Serial("no such device".to_string()));
+    }
+}
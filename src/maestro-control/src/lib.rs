@@ -1,12 +1,59 @@
+//! Serial control of Pololu Maestro servo controllers.
+//!
+//! Enable the `tracing` feature to log every command sent at `trace` level and failed commands at `warn` level.
+//!
+//! With `default-features = false` the crate is `no_std` and only the [`protocol`] encoders are
+//! available, for hosts that talk to a Maestro over their own serial link.
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
+pub mod protocol;
+#[cfg(feature = "std")]
 mod maestro;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod channel;
+#[cfg(feature = "std")]
+mod conversion;
+#[cfg(feature = "std")]
 mod error;
+#[cfg(feature = "std")]
+mod transport;
+#[cfg(feature = "std")]
+mod units;
+#[cfg(feature = "std")]
+mod simulation;
 
-pub use maestro::Maestro;
+#[cfg(feature = "std")]
+pub use maestro::{Maestro, ACCEL_MAX, SPEED_MAX};
+#[cfg(feature = "std")]
+pub use builder::MaestroBuilder;
+#[cfg(feature = "std")]
+pub use channel::Channel;
+#[cfg(feature = "std")]
+pub use units::{Acceleration, Speed};
+#[cfg(feature = "std")]
+pub use conversion::{degrees_to_quarter_us, quarter_us_to_degrees};
+#[cfg(feature = "std")]
+pub use transport::{MaestroTransport, MemoryTransport};
+#[cfg(feature = "std")]
 pub use maestro::MovingState;
+#[cfg(feature = "std")]
+pub use maestro::BoardStatus;
+#[cfg(feature = "std")]
+pub use maestro::DeviceInfo;
+#[cfg(feature = "std")]
+pub use maestro::ScriptStatus;
+#[cfg(feature = "std")]
+pub use maestro::DropBehavior;
+#[cfg(feature = "std")]
+pub use maestro::LimitViolation;
+#[cfg(feature = "std")]
+pub use maestro::available_ports;
+#[cfg(feature = "std")]
+pub use error::{MaestroError, MaestroErrorFlags};
 
-
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -14,4 +61,14 @@ mod tests {
     fn test_maestro_error() {
         assert!(Maestro::new("COM0").is_err())
     }
+
+    #[test]
+    fn test_maestro_baud_rate_error() {
+        assert!(Maestro::with_baud_rate("COM0", 115200).is_err())
+    }
+
+    #[test]
+    fn test_maestro_device_number_out_of_range() {
+        assert!(matches!(Maestro::with_device_number("COM0", 200), Err(MaestroError::OutOfBounds)))
+    }
 }
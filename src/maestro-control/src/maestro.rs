@@ -1,237 +1,2329 @@
-use std::io::{Read, Write};
-use std::time::Duration;
-use serialport::SerialPort;
-use crate::error::MaestroError;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use serialport::{SerialPortInfo, SerialPortType};
+use crate::builder::{MaestroBuilder, DEFAULT_CHANNEL_COUNT, DEFAULT_TIMEOUT};
+use crate::channel::Channel;
+use crate::conversion::{degrees_to_quarter_us, quarter_us_to_degrees};
+use crate::error::{MaestroError, MaestroErrorFlags};
+use crate::protocol::{self, crc7};
+use crate::simulation::SimulatedTransport;
+use crate::transport::{ClosedTransport, MaestroTransport};
+use crate::units::{Acceleration, Speed};
+
+/// Largest speed limit accepted by `set_speed_raw`, in units of (0.25µs)/(10ms).
+pub const SPEED_MAX: u16 = 0x3FFF;
+/// Largest acceleration limit accepted by `set_acceleration_raw`, in units of (0.25µs)/(10ms)/(80ms).
+pub const ACCEL_MAX: u8 = u8::MAX;
+/// How long to wait before each reconnect attempt unless changed with `set_reconnect_delay`.
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_millis(250);
+/// How often `home_and_verify` polls the moving state.
+const HOME_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How close a computed `sweep` step may come to the final angle before it is dropped as a duplicate.
+const SWEEP_EPSILON: f64 = 1e-9;
 
 /// Core of control program. Stores the serial port connection to pass to all other values.
 ///
+/// Any [`MaestroTransport`] can stand in for the serial port through `Maestro::from_transport`.
+///
 /// # Example:
 /// ```
 /// use maestro_control::Maestro;
 /// let mut maestro = Maestro::new("COM1");
 /// ```
+///
+/// Use [`MaestroBuilder`] to configure the baud rate, timeout and device number together.
 pub struct Maestro {
-    serial_port: Box<dyn SerialPort>
+    link: Arc<Mutex<Link>>,
+    pub(crate) device_number: Option<u8>,
+    pub(crate) channel_count: u8,
+    drop_behavior: DropBehavior,
+    crc_enabled: bool,
+    limits: HashMap<u8, (u16, u16)>,
+    limit_violation: LimitViolation,
+    homes: HashMap<u8, u16>,
+    queue: Option<CommandQueue>
 }
 
-const BAUD_RATE: u32 = 9600;
-
 impl Maestro {
-    /// Opens the Maestro at the given serial port.
+    /// Opens the Maestro at the given serial port using the default baud rate of 9600.
     ///
     /// `port` should be a valid serial port.
     ///
     /// Ports are opened in exclusive mode and are not released until the `Maestro` instance is dropped.
     /// # Errors
-    /// - `UnableToConnect` if serial connection was unable to be established.
+    /// - `Serial` if the serial port could not be opened
     pub fn new(port: &str) -> Result<Self, MaestroError> {
-        let sp = serialport::new(port, BAUD_RATE).timeout(Duration::from_millis(10)).open();
-        return if let Ok(serial_port) = sp {
-            Ok(Maestro {
-                serial_port,
-            })
-        } else {
-            Err(MaestroError::UnableToConnect)
+        MaestroBuilder::new().port(port).build()
+    }
+
+    /// Opens the Maestro at the given serial port using a specific baud rate.
+    ///
+    /// `port` should be a valid serial port.
+    /// `baud` should match the rate configured in the Maestro Control Center (up to 200000).
+    ///
+    /// Ports are opened in exclusive mode and are not released until the `Maestro` instance is dropped.
+    /// # Errors
+    /// - `Serial` if the serial port could not be opened
+    pub fn with_baud_rate(port: &str, baud: u32) -> Result<Self, MaestroError> {
+        MaestroBuilder::new().port(port).baud(baud).build()
+    }
+
+    /// Opens the Maestro at the given serial port using a specific baud rate and read/write timeout.
+    ///
+    /// `port` should be a valid serial port.
+    /// `baud` should match the rate configured in the Maestro Control Center (up to 200000).
+    /// `timeout` is how long reads and writes wait before failing. See [`Maestro::set_timeout`].
+    ///
+    /// Ports are opened in exclusive mode and are not released until the `Maestro` instance is dropped.
+    /// # Errors
+    /// - `Serial` if the serial port could not be opened
+    pub fn with_timeout(port: &str, baud: u32, timeout: Duration) -> Result<Self, MaestroError> {
+        MaestroBuilder::new().port(port).baud(baud).timeout(timeout).build()
+    }
+
+    /// Opens the Maestro at the given serial port and addresses it with the Pololu protocol.
+    ///
+    /// Use this when several Maestros share a serial line. Every command is prefixed with `0xAA` and
+    /// `device_number`, so only the board configured with that device number responds.
+    ///
+    /// `port` should be a valid serial port.
+    /// `device_number` should be 0 <= x <= 127
+    /// # Errors
+    /// - `OutOfBounds` if device_number is greater than 127
+    /// - `Serial` if the serial port could not be opened
+    pub fn with_device_number(port: &str, device_number: u8) -> Result<Self, MaestroError> {
+        MaestroBuilder::new().port(port).device_number(device_number).build()
+    }
+
+    /// Creates a Maestro that communicates over the given transport instead of opening a serial port.
+    ///
+    /// Pair with [`crate::MemoryTransport`] to exercise the protocol without hardware.
+    pub fn from_transport<T: MaestroTransport + 'static>(transport: T) -> Self {
+        Maestro {
+            link: Arc::new(Mutex::new(Link {
+                transport: Box::new(transport),
+                connection: None,
+                reconnect_retries: 0,
+                reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            })),
+            device_number: None,
+            channel_count: DEFAULT_CHANNEL_COUNT,
+            drop_behavior: DropBehavior::Hold,
+            crc_enabled: false,
+            limits: HashMap::new(),
+            limit_violation: LimitViolation::Error,
+            homes: HashMap::new(),
+            queue: None,
+        }
+    }
+
+    /// Creates a Maestro backed by a simulated 12-channel board instead of hardware.
+    ///
+    /// The simulation tracks each channel's target and speed, moves positions toward their targets
+    /// in real time, and answers `get_position` and `get_moving_state` to match. Accelerations, PWM,
+    /// scripts and `go_home` are accepted but have no effect. CRC bytes and device numbers are
+    /// accepted but not checked.
+    ///
+    /// # Example:
+    /// ```
+    /// use maestro_control::Maestro;
+    ///
+    /// let mut maestro = Maestro::simulated();
+    /// maestro.set_target(0, 6000).unwrap();
+    /// assert_eq!(maestro.get_position_raw(0).unwrap(), 6000);
+    /// assert!(!maestro.is_moving().unwrap());
+    /// ```
+    pub fn simulated() -> Self {
+        Self::from_transport(SimulatedTransport::new(DEFAULT_TIMEOUT))
+    }
+
+    /// Sets the read/write timeout of the underlying serial port.
+    ///
+    /// Commands that expect a response (such as `get_position`) fail with `UnableToReceive` if the
+    /// full response does not arrive within this time, so a timeout that is too short for the link
+    /// shows up as intermittent `UnableToReceive` errors.
+    /// # Errors:
+    /// - `UnableToConnect` if the serial port rejected the new timeout
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), MaestroError> {
+        self.link.lock().unwrap().transport.set_timeout(timeout).map_err(|_| MaestroError::UnableToConnect)
+    }
+
+    /// The number of channels on the board, as configured when the `Maestro` was created.
+    pub fn channel_count(&self) -> u8 {
+        self.channel_count
+    }
+
+    /// USB details of the port the Maestro was opened on.
+    ///
+    /// Returns `None` if the `Maestro` was created with `from_transport`, the port is not a USB device,
+    /// or the system's ports could not be listed. Pololu's vendor ID is `0x1FFB`; the product ID
+    /// identifies the board size (`0x0089` for the Micro Maestro 6, `0x008A` to `0x008C` for the Mini
+    /// Maestro 12, 18 and 24).
+    pub fn device_info(&self) -> Option<DeviceInfo> {
+        let port = self.link.lock().unwrap().connection.as_ref()?.port.clone();
+        find_device_info(&port, serialport::available_ports().ok()?)
+    }
+
+    /// Records the serial port settings used to reconnect.
+    pub(crate) fn set_connection(&mut self, connection: Connection) {
+        self.link.lock().unwrap().connection = Some(connection);
+    }
+
+    /// Checks a channel number against this board's channel count.
+    /// # Errors:
+    /// - `InvalidChannel` if index is not below the board's channel count
+    pub fn channel(&self, index: u8) -> Result<Channel, MaestroError> {
+        Channel::with_channel_count(index, self.channel_count)
+    }
+
+    /// Enables or disables appending a CRC-7 byte to every command.
+    ///
+    /// This must match the "Enable CRC" serial setting on the board; with CRC enabled on the board,
+    /// commands without a valid CRC byte are ignored. Mini-SSC commands never carry a CRC.
+    pub fn enable_crc(&mut self, on: bool) {
+        self.crc_enabled = on;
+    }
+
+    /// Sets how many times to reopen the serial port when a command fails to send or the port
+    /// reports it is disconnected.
+    ///
+    /// Each attempt waits for the reconnect delay (250ms by default, see `set_reconnect_delay`) to
+    /// give a USB device time to come back, then reopens the port and sends the command again. Any
+    /// response that was in flight when the link failed is lost. A response that merely times out
+    /// (`UnableToReceive`) does not trigger a reconnect. Only a `Maestro` opened from a serial port
+    /// name can reconnect; this has no effect on one created with `from_transport`. Defaults to 0,
+    /// which disables reconnection.
+    pub fn set_auto_reconnect(&mut self, retries: u8) {
+        self.link.lock().unwrap().reconnect_retries = retries;
+    }
+
+    /// Sets how long to wait before each reconnect attempt. Defaults to 250ms.
+    pub fn set_reconnect_delay(&mut self, delay: Duration) {
+        self.link.lock().unwrap().reconnect_delay = delay;
+    }
+
+    /// Buffers commands on the host and paces writes to what the serial line can carry.
+    ///
+    /// With a queue, commands that have no response return as soon as they are queued and a
+    /// background thread writes them. After each write the thread waits as long as the line takes to
+    /// carry it at `bytes_per_second`, and everything queued meanwhile goes out together, in order, in
+    /// the next write. Commands that read a response, and `flush`, wait for everything queued to be
+    /// written first. `emergency_stop` discards queued commands. Queued commands are flushed when the
+    /// `Maestro` is dropped.
+    ///
+    /// Background writes reconnect and retry like any other command when auto-reconnect is enabled.
+    /// A background write that still fails discards what it was writing, and the error is returned by
+    /// the next queued command or `flush`.
+    ///
+    /// With the usual 8 data bits, no parity and 1 stop bit the line carries `baud / 10` bytes per
+    /// second. `None` turns the queue off, flushing anything still queued.
+    /// # Errors:
+    /// - `OutOfBounds` if bytes_per_second is 0
+    /// - `UnableToSend` if serial port was unable to send queued commands
+    pub fn set_command_queue(&mut self, bytes_per_second: Option<u32>) -> Result<(), MaestroError> {
+        if bytes_per_second == Some(0) { return Err(MaestroError::OutOfBounds) }
+        let flushed = self.flush();
+        self.queue = bytes_per_second.map(|bytes_per_second| CommandQueue::new(self.link.clone(), bytes_per_second));
+        flushed
+    }
+
+    /// Waits until every command in the command queue has been written.
+    ///
+    /// Does nothing without a queue.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send a queued command to Maestro
+    /// - `Disconnected` if the serial port went away while sending a queued command
+    pub fn flush(&mut self) -> Result<(), MaestroError> {
+        match &self.queue {
+            Some(queue) => queue.wait_until_written(),
+            None => Ok(())
         }
     }
 
-    /// Sets the acceleration of a single channel.
+    /// Sets what happens to the servos when this `Maestro` is dropped.
+    ///
+    /// Defaults to `DropBehavior::Hold`. Errors while sending the drop command are ignored.
+    pub fn set_drop_behavior(&mut self, behavior: DropBehavior) {
+        self.drop_behavior = behavior;
+    }
+
+    /// Restricts the positions a single channel can be commanded to.
+    ///
+    /// Limits are enforced on the host by `set_position`, `set_target` and `set_multiple_targets`.
+    /// What happens to a target outside the limits is set with `set_limit_violation`.
+    /// A target of 0, which turns the output off, is always allowed.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `min_deg` and `max_deg` should be degrees 0 <= x <= 180 with `min_deg <= max_deg`
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if the limits are outside 0 to 180 degrees or `min_deg > max_deg`
+    pub fn set_channel_limits(&mut self, channel: u8, min_deg: f64, max_deg: f64) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        if min_deg > max_deg { return Err(MaestroError::OutOfBounds) }
+        let min = degrees_to_quarter_us(min_deg)?;
+        let max = degrees_to_quarter_us(max_deg)?;
+        self.limits.insert(channel, (min, max));
+        Ok(())
+    }
+
+    /// Stores the home of a single channel without sending it.
+    ///
+    /// `rehome_channel` and `home_and_verify` send the stored home; `home_channel` stores and sends it
+    /// in one call. The home is stored on the host only; it is unrelated to the home positions
+    /// configured on the board and used by `go_home`. It is checked against the channel's limits when
+    /// it is sent.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `degree` should be 0 <= x <= 180
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if the degree is outside 0 to 180
+    pub fn set_channel_home(&mut self, channel: u8, degree: f64) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        let home = degrees_to_quarter_us(degree)?;
+        self.homes.insert(channel, home);
+        Ok(())
+    }
+
+    /// Sets whether targets outside a channel's limits are clamped or rejected.
+    ///
+    /// Defaults to `LimitViolation::Error`.
+    pub fn set_limit_violation(&mut self, violation: LimitViolation) {
+        self.limit_violation = violation;
+    }
+
+    /// Limits how quickly a single channel changes speed.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// An acceleration of `Acceleration::UNLIMITED` means no acceleration limit.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_acceleration(&mut self, channel: u8, acceleration: Acceleration) -> Result<(), MaestroError> {
+        self.set_acceleration_raw(channel, acceleration.raw())
+    }
+
+    /// Sets the acceleration of a single channel from a raw Maestro value.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `acceleration` is in units of (0.25µs)/(10ms)/(80ms), up to `ACCEL_MAX`.
+    /// An acceleration of 0 means no acceleration limit.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_acceleration_raw(&mut self, channel: u8, acceleration: u8) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        self.send_command_no_response(&form_data(0x89, channel, acceleration.into())?)
+    }
+
+    /// Sets the acceleration of a single channel. See `set_acceleration`.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is not below the board's channel count
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_acceleration_on(&mut self, channel: Channel, acceleration: Acceleration) -> Result<(), MaestroError> {
+        self.set_acceleration(channel.index(), acceleration)
+    }
+
+    /// Limits how fast a single channel moves toward its target.
     ///
-    /// `channel` should be a valid channel < 12.
-    /// `acceleration` is any u8
+    /// `channel` should be a valid channel below the board's channel count.
+    /// A speed of `Speed::UNLIMITED` means no speed limit.
     /// # Errors:
     /// - `InvalidChannel` if channel is out of range
     /// - `UnableToSend` if serial port was unable to send command to Maestro
-    pub fn set_acceleration(&mut self, channel: u8, acceleration: u8) -> Result<(), MaestroError> {
-        verify_channel_range(channel)?;
-        self.send_command_no_response(&form_data(0x84, channel, acceleration as u16))
+    pub fn set_speed(&mut self, channel: u8, speed: Speed) -> Result<(), MaestroError> {
+        self.set_speed_raw(channel, speed.raw())
     }
 
-    /// Sets the speed of a single channel.
+    /// Sets the speed of a single channel from a raw Maestro value.
     ///
-    /// `channel` should be a valid channel < 12.
-    /// `speed` is any u8
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `speed` should be 0 <= x <= `SPEED_MAX`, in units of (0.25µs)/(10ms).
+    /// A speed of 0 means no speed limit.
     /// # Errors:
     /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if speed is greater than `SPEED_MAX`
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_speed_raw(&mut self, channel: u8, speed: u16) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        if speed > SPEED_MAX { return Err(MaestroError::OutOfBounds) }
+        self.send_command_no_response(&form_data(0x87, channel, speed)?)
+    }
+
+    /// Sets the speed of a single channel. See `set_speed`.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is not below the board's channel count
     /// - `UnableToSend` if serial port was unable to send command to Maestro
-    pub fn set_speed(&mut self, channel: u8, speed: u8) -> Result<(), MaestroError> {
-        verify_channel_range(channel)?;
-        self.send_command_no_response(&form_data(0x84, channel, speed as u16))
+    pub fn set_speed_on(&mut self, channel: Channel, speed: Speed) -> Result<(), MaestroError> {
+        self.set_speed(channel.index(), speed)
     }
 
     /// Sets the position of a single channel.
     ///
-    /// `channel` should be a valid channel < 12.
+    /// Degrees are converted with [`crate::degrees_to_quarter_us`].
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
     /// `positions` should be a degree 0 <= x <= 180
     /// # Errors:
     /// - `InvalidChannel` if channel is out of range
+    /// - `LimitExceeded` if the position is outside the channel's limits and violations are errors
     /// - `UnableToSend` if serial port was unable to send command to Maestro
     pub fn set_position(&mut self, channel: u8, degree: f64) -> Result<(), MaestroError> {
-        verify_channel_range(channel)?;
-        let data = convert_deg_to_quarter_micros(degree)?;
-        self.send_command_no_response(&form_data(0x84, channel, data))
+        let data = degrees_to_quarter_us(degree)?;
+        self.set_target(channel, data)
     }
 
-    /// Gets the position of a single channel.
+    /// Sets the position of a single channel in degrees. See `set_position`.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is not below the board's channel count
+    /// - `LimitExceeded` if the position is outside the channel's limits and violations are errors
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_position_on(&mut self, channel: Channel, degree: f64) -> Result<(), MaestroError> {
+        self.set_position(channel.index(), degree)
+    }
+
+    /// Moves a single channel with the given speed and acceleration.
     ///
-    /// `channel` should be a valid channel < 12.
+    /// The acceleration, speed and target commands are sent together in a single write, in that order,
+    /// so the move always starts with the new profile. The profile stays in effect for later moves.
     ///
-    /// Returns the position in degrees.
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `degree` should be 0 <= x <= 180 and `speed` 0 <= x <= `SPEED_MAX`. See `set_speed_raw` and
+    /// `set_acceleration_raw` for their units.
     /// # Errors:
     /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if the degree or speed is out of range, in which case nothing is sent
+    /// - `LimitExceeded` if the position is outside the channel's limits and violations are errors
     /// - `UnableToSend` if serial port was unable to send command to Maestro
-    /// - `UnableToReceive` if Maestro sends back invalid data
-    pub fn get_position(&mut self, channel: u8) -> Result<f64, MaestroError> {
-        verify_channel_range(channel)?;
-        let pos = self.send_command(&[0x90, channel])?;
-        return Ok(convert_int_to_deg(pos));
+    pub fn move_with_profile(&mut self, channel: u8, degree: f64, speed: u16, acceleration: u8) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        if speed > SPEED_MAX { return Err(MaestroError::OutOfBounds) }
+        let target = self.apply_limits(channel, degrees_to_quarter_us(degree)?)?;
+        let mut data = self.frame(&form_data(0x89, channel, acceleration.into())?);
+        data.extend(self.frame(&form_data(0x87, channel, speed)?));
+        data.extend(self.frame(&form_data(0x84, channel, target)?));
+        self.transmit(&data, &mut [])
     }
 
-    /// Set the accelerations of all channels in vector.
+    /// Moves a group of channels together, each with its own speed limit.
     ///
-    /// `channels` should be a vector of valid channels < 12.
+    /// Every `(channel, degree, speed)` sets the channel's speed first, then all targets are sent. If
+    /// the channels form a contiguous block they share one Set Multiple Targets command so the moves
+    /// start together; otherwise each gets its own Set Target command. Everything goes out in a single
+    /// write, and nothing is sent if any move is invalid.
+    ///
+    /// Channels should be distinct valid channels below the board's channel count.
+    /// Degrees should be 0 <= x <= 180 and speeds 0 <= x <= `SPEED_MAX`.
     /// # Errors:
-    /// - `InvalidChannel` if channel is out of range
+    /// - `InvalidChannel` if a channel is out of range or appears more than once
+    /// - `OutOfBounds` if a degree or speed is out of range
+    /// - `LimitExceeded` if a position is outside its channel's limits and violations are errors
     /// - `UnableToSend` if serial port was unable to send command to Maestro
-    pub fn set_accelerations(&mut self, channels: Vec<u8>, accelerations: Vec<u8>) -> Result<(), MaestroError> {
-        for (channel, accel) in channels.into_iter().zip(accelerations.into_iter()) {
-            self.set_acceleration(channel, accel)?;
+    pub fn move_group(&mut self, moves: &[(u8, f64, u16)]) -> Result<(), MaestroError> {
+        let mut targets = Vec::with_capacity(moves.len());
+        let mut data = Vec::with_capacity(moves.len() * 6 + 3);
+        for &(channel, degree, speed) in moves {
+            verify_channel_range(channel, self.channel_count)?;
+            if speed > SPEED_MAX { return Err(MaestroError::OutOfBounds) }
+            let target = self.apply_limits(channel, degrees_to_quarter_us(degree)?)?;
+            data.extend(self.frame(&form_data(0x87, channel, speed)?));
+            targets.push((channel, target));
         }
-        Ok(())
+        targets.sort_unstable_by_key(|&(channel, _)| channel);
+        if targets.windows(2).any(|pair| pair[0].0 == pair[1].0) { return Err(MaestroError::InvalidChannel) }
+        let contiguous = targets.windows(2).all(|pair| pair[1].0 == pair[0].0 + 1);
+        match targets.first() {
+            None => return Ok(()),
+            Some(&(first_channel, _)) if contiguous => {
+                let mut command = vec![0x9F, targets.len() as u8, first_channel];
+                for &(_, target) in &targets {
+                    command.extend(split_data(target)?);
+                }
+                data.extend(self.frame(&command));
+            }
+            Some(_) => {
+                for &(channel, target) in &targets {
+                    data.extend(self.frame(&form_data(0x84, channel, target)?));
+                }
+            }
+        }
+        self.transmit(&data, &mut [])
     }
 
-    /// Sets the speeds of all channels in vector.
+    /// Sets the target of a single channel in quarter-microseconds.
     ///
-    /// `channels` should be a vector of valid channels < 12.
+    /// The value is sent as-is, matching the targets shown in the Maestro Control Center.
+    /// A target of 0 turns the channel's output off.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
     /// # Errors:
     /// - `InvalidChannel` if channel is out of range
+    /// - `LimitExceeded` if the target is outside the channel's limits and violations are errors
+    /// - `OutOfBounds` if the target is greater than 16383
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_target(&mut self, channel: u8, quarter_us: u16) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        let target = self.apply_limits(channel, quarter_us)?;
+        self.send_command_no_response(&form_data(0x84, channel, target)?)
+    }
+
+    /// Sets the target of a single channel in quarter-microseconds. See `set_target`.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is not below the board's channel count
+    /// - `LimitExceeded` if the target is outside the channel's limits and violations are errors
+    /// - `OutOfBounds` if the target is greater than 16383
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_target_on(&mut self, channel: Channel, quarter_us: u16) -> Result<(), MaestroError> {
+        self.set_target(channel.index(), quarter_us)
+    }
+
+    /// Sets the targets of a contiguous block of channels in a single command.
+    ///
+    /// `first_channel` is the first channel to set; `targets` are applied to it and the channels that follow.
+    /// Targets are raw values in quarter-microseconds. Nothing is sent if `targets` is empty.
+    /// # Errors:
+    /// - `InvalidChannel` if `first_channel` is out of range or `first_channel + targets.len()` runs
+    ///   past the last channel
+    /// - `LimitExceeded` if a target is outside its channel's limits and violations are errors
+    /// - `OutOfBounds` if a target is greater than 16383
     /// - `UnableToSend` if serial port was unable to send command to Maestro
-    pub fn set_speeds(&mut self, channels: Vec<u8>, speeds: Vec<u8>) -> Result<(), MaestroError> {
-        for (channel, speed) in channels.into_iter().zip(speeds.into_iter()) {
-            self.set_speed(channel, speed)?;
+    pub fn set_multiple_targets(&mut self, first_channel: u8, targets: &[u16]) -> Result<(), MaestroError> {
+        verify_channel_range(first_channel, self.channel_count)?;
+        if first_channel as usize + targets.len() > self.channel_count as usize {
+            return Err(MaestroError::InvalidChannel);
         }
-        Ok(())
+        if targets.is_empty() { return Ok(()) }
+        let mut data = Vec::with_capacity(3 + 2 * targets.len());
+        data.extend([0x9F, targets.len() as u8, first_channel]);
+        for (channel, &target) in (first_channel..).zip(targets) {
+            let target = self.apply_limits(channel, target)?;
+            data.extend(split_data(target)?);
+        }
+        self.send_command_no_response(&data)
     }
 
-    /// Sets the positions of all channels in vector.
+    /// Sets the position of a single channel using the Mini-SSC protocol.
     ///
-    /// `channels` should be a vector of valid channels < 12.
-    /// `positions` should be a degree 0 <= x <= 180
+    /// This is independent of the Pololu-native commands used by `set_position` and is intended for
+    /// boards and controllers that only speak Mini-SSC.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `value` should be 0 <= x <= 254, mapped by the board across the channel's configured range.
     /// # Errors:
     /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if value is 255, which is reserved as the Mini-SSC sync byte
     /// - `UnableToSend` if serial port was unable to send command to Maestro
-    pub fn set_positions(&mut self, channels: Vec<u8>, positions: Vec<f64>) -> Result<(), MaestroError> {
-        for (channel, pos) in channels.into_iter().zip(positions.into_iter()) {
-            self.set_position(channel, pos)?;
+    pub fn set_position_mini_ssc(&mut self, channel: u8, value: u8) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        if value == 0xFF { return Err(MaestroError::OutOfBounds) }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(channel, value, "sending Mini-SSC command");
+        self.transmit(&[0xFF, channel, value], &mut [])
+    }
+
+    /// Turns off the output of every listed channel, letting the servos go limp.
+    ///
+    /// Each channel is sent a target of 0 in a single write. Setting any nonzero target on a channel
+    /// turns its output back on.
+    ///
+    /// `channels` should be valid channels below the board's channel count.
+    /// # Errors:
+    /// - `InvalidChannel` if a channel is out of range, in which case nothing is sent
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn disable_all(&mut self, channels: &[u8]) -> Result<(), MaestroError> {
+        self.send_uniform(0x84, channels, 0)
+    }
+
+    /// Turns off the output of every channel at once, letting all servos go limp.
+    ///
+    /// A Set Target command with a target of 0 for each channel is sent immediately in a single
+    /// write, discarding any commands waiting in the command queue. Set Target is used rather than Set
+    /// Multiple Targets because the Micro Maestro does not support the latter. Channel limits and
+    /// homes are ignored. Setting any nonzero target on a channel turns its output back on.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn emergency_stop(&mut self) -> Result<(), MaestroError> {
+        let mut frame = Vec::with_capacity(self.channel_count as usize * 4);
+        for channel in 0..self.channel_count {
+            frame.extend(self.frame(&[0x84, channel, 0, 0]));
         }
-        Ok(())
+        if let Some(queue) = &self.queue {
+            queue.clear();
+        }
+        self.transmit_now(&frame, &mut [], 1)
     }
 
-    /// Gets the positions of all channels in vector.
+    /// Gets the position of a single channel.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
     ///
-    /// `channels` should be a vector of valid channels < 12.
+    /// Returns the position in degrees, using the same scale as `set_position`. Use `get_position_raw`
+    /// for the raw value reported by the Maestro.
     /// # Errors:
     /// - `InvalidChannel` if channel is out of range
+    /// - `ChannelDisabled` if the channel has no target (its output is off)
     /// - `UnableToSend` if serial port was unable to send command to Maestro
     /// - `UnableToReceive` if Maestro sends back invalid data
-    pub fn get_pos_motors(&mut self, channels: Vec<u8>) -> Result<Vec<f64>, MaestroError> {
-        let mut motor_positions: Vec<f64> = Vec::with_capacity(channels.len());
-        for channel in channels {
-            motor_positions.push(self.get_position(channel)?);
-        }
-        Ok(motor_positions)
+    pub fn get_position(&mut self, channel: u8) -> Result<f64, MaestroError> {
+        let pos = self.get_position_raw(channel)?;
+        if pos == 0 { return Err(MaestroError::ChannelDisabled) }
+        Ok(quarter_us_to_degrees(pos))
     }
 
-    /// Check if any of the servos are currently moving.
+    /// Gets the raw position of a single channel in quarter-microseconds.
     ///
-    /// Returns `MovingState::ServosMoving` if any servos are currently moving, otherwise returning `MovingState::ServoStopped`.
+    /// `channel` should be a valid channel below the board's channel count.
     ///
-    /// # Error
-    /// - `UnableToSend` if serialport was unable to send the command to Maestro.
-    pub fn get_moving_state(&mut self) -> Result<MovingState, MaestroError> {
-        let res = self.send_command(&[0x93])?;
-        return match res {
-            0 => Ok(MovingState::ServosStopped),
-            1 => Ok(MovingState::ServosMoving),
-            _ => Err(MaestroError::InvalidMovingState)
-        }
+    /// A value of 0 means the channel has no target and its output is off.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn get_position_raw(&mut self, channel: u8) -> Result<u16, MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        self.send_command_u16(&[0x90, channel])
     }
 
-    fn send_command_no_response(&mut self, data: &[u8]) -> Result<(), MaestroError> {
-        let res = self.serial_port.write(data);
-        if res.is_err() {
-            return Err(MaestroError::UnableToSend);
-        }
-        Ok(())
+    /// Sets the output of the Maestro's dedicated PWM pin.
+    ///
+    /// Only boards with a PWM-capable pin (the Mini Maestro 12, 18 and 24) support this, and the
+    /// channel must be configured as an output in the Maestro Control Center.
+    ///
+    /// `on_time` and `period` are in units of 1/48 microseconds. An `on_time` of 0 turns the output off.
+    /// # Errors:
+    /// - `OutOfBounds` if on_time or period is greater than 16383
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_pwm(&mut self, on_time: u16, period: u16) -> Result<(), MaestroError> {
+        let [on_low, on_high] = split_data(on_time)?;
+        let [period_low, period_high] = split_data(period)?;
+        self.send_command_no_response(&[0x8A, on_low, on_high, period_low, period_high])
+    }
+
+    /// Stores the home of a single channel and sends the channel there.
+    ///
+    /// The home is stored on the host only; it is unrelated to the home positions configured on the
+    /// board and used by `go_home`. Calling this again with the same home has no further effect, so it
+    /// is safe to repeat.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `home_degrees` should be 0 <= x <= 180
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if the degree is greater than 180
+    /// - `LimitExceeded` if the home is outside the channel's limits and violations are errors, in
+    ///   which case it is not stored
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn home_channel(&mut self, channel: u8, home_degrees: u16) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        let home = degrees_to_quarter_us(home_degrees.into())?;
+        self.apply_limits(channel, home)?;
+        self.homes.insert(channel, home);
+        self.set_target(channel, home)
+    }
+
+    /// Sends a single channel back to the home stored with `home_channel` or `set_channel_home`.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `HomeNotSet` if no home was stored for the channel
+    /// - `LimitExceeded` if the home is outside the channel's limits and violations are errors
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn rehome_channel(&mut self, channel: u8) -> Result<(), MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        let home = *self.homes.get(&channel).ok_or(MaestroError::HomeNotSet)?;
+        self.set_target(channel, home)
     }
 
-    fn send_command(&mut self, data: &[u8]) -> Result<i32, MaestroError> {
-        let res = self.serial_port.write(data);
-        if res.is_err() {
-            return Err(MaestroError::UnableToSend);
+    /// Sends the listed channels to the homes set with `set_channel_home` and checks they got there.
+    ///
+    /// All homes are sent in a single write. Once the servos stop moving, each channel's position is
+    /// read back and compared with its home; a home clamped by the channel's limits is compared with
+    /// the clamped value. Positions are only what the Maestro is commanding, so a stalled servo
+    /// without feedback still passes.
+    ///
+    /// `channels` should be valid channels below the board's channel count.
+    /// `tolerance_deg` is how many degrees a position may differ from its home.
+    /// # Errors:
+    /// - `InvalidChannel` if a channel is out of range, in which case nothing is sent
+    /// - `HomeNotSet` if a channel has no home, in which case nothing is sent
+    /// - `LimitExceeded` if a home is outside its channel's limits and violations are errors
+    /// - `MoveTimeout` if the servos were still moving when `timeout` elapsed
+    /// - `HomeVerificationFailed` with the first channel that is not within tolerance of its home
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn home_and_verify(&mut self, channels: &[u8], tolerance_deg: f64, timeout: Duration) -> Result<(), MaestroError> {
+        let mut targets = Vec::with_capacity(channels.len());
+        let mut data = Vec::with_capacity(channels.len() * 4);
+        for &channel in channels {
+            verify_channel_range(channel, self.channel_count)?;
+            let home = *self.homes.get(&channel).ok_or(MaestroError::HomeNotSet)?;
+            let target = self.apply_limits(channel, home)?;
+            data.extend(self.frame(&form_data(0x84, channel, target)?));
+            targets.push(target);
         }
-        let buf: &mut[u8; 2] = &mut [0; 2];
-        let r = self.serial_port.read_exact(buf);
-        if let Err(_) = r {
-            return Err(MaestroError::UnableToReceive)
+        if data.is_empty() { return Ok(()) }
+        self.transmit(&data, &mut [])?;
+        self.wait_until_stopped(HOME_POLL_INTERVAL, Some(timeout))?;
+        let positions = self.read_positions_raw(channels)?;
+        for ((&channel, target), position) in channels.iter().zip(targets).zip(positions) {
+            let error = quarter_us_to_degrees(position) - quarter_us_to_degrees(target);
+            if error.abs() > tolerance_deg {
+                return Err(MaestroError::HomeVerificationFailed(channel));
+            }
         }
-        Ok(buf[0] as i32 + 256 * buf[1] as i32)
+        Ok(())
     }
-}
 
-/// Returned enum based on current servo status.
-///
-/// # Example:
-/// ```
-/// use maestro_control::{Maestro, MovingState};
-///
-/// let mut m = Maestro::new("COM1");
-/// if let Ok(mut maestro) = m {
-///     match maestro.get_moving_state() {
-///         Ok(MovingState::ServosMoving) => println!("Servos still moving!"),
-///         Ok(MovingState::ServosStopped) => println!("All servos have stopped moving!"),
-///         Err(e) => println!("{:?}", e),
-///     }
-/// }
-/// ```
-pub enum MovingState {
-    /// Is returned if servos are still moving
-    ServosMoving,
-    /// Is returned if all servos have stopped moving
-    ServosStopped
-}
+    /// Sends every servo to its configured home position.
+    ///
+    /// Home positions are configured on the board in the Maestro Control Center.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn go_home(&mut self) -> Result<(), MaestroError> {
+        self.send_command_no_response(&[0xA2])
+    }
 
-fn form_data(command: u8, channel: u8, data:u16) -> [u8; 4] {
-    [command, channel, (data & 0x7F) as u8, ((data >> 7) & 0x7F) as u8]
-}
+    /// Stops the script running on the Maestro.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn stop_script(&mut self) -> Result<(), MaestroError> {
+        self.send_command_no_response(&[0xA4])
+    }
 
-const MAX_CHANNEL: u8 = 11;
+    /// Restarts the Maestro's script at a subroutine.
+    ///
+    /// `subroutine` is the subroutine's number, 0 <= x <= 127, in the order they appear in the script.
+    /// # Errors:
+    /// - `OutOfBounds` if subroutine is greater than 127
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn restart_script_at(&mut self, subroutine: u8) -> Result<(), MaestroError> {
+        verify_subroutine(subroutine)?;
+        self.send_command_no_response(&[0xA7, subroutine])
+    }
 
-fn verify_channel_range(channel: u8) -> Result<(), MaestroError> {
-    return if channel > MAX_CHANNEL {
-        Err(MaestroError::InvalidChannel)
-    } else {
-        Ok(())
+    /// Restarts the Maestro's script at a subroutine, placing `param` on the script's stack first.
+    ///
+    /// `subroutine` is the subroutine's number, 0 <= x <= 127, in the order they appear in the script.
+    /// `param` should be 0 <= x <= 16383
+    /// # Errors:
+    /// - `OutOfBounds` if subroutine is greater than 127 or param is greater than 16383
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn restart_script_at_with_param(&mut self, subroutine: u8, param: u16) -> Result<(), MaestroError> {
+        verify_subroutine(subroutine)?;
+        let [low, high] = split_data(param)?;
+        self.send_command_no_response(&[0xA8, subroutine, low, high])
     }
-}
 
-fn convert_deg_to_quarter_micros(deg: f64) -> Result<u16, MaestroError> {
-    if deg < 0.0 || deg > 180.0 { return Err(MaestroError::OutOfBounds) }
-    return Ok((deg * 44.444) as u16 + 1984)
-}
+    /// Checks whether the Maestro's script is running.
+    ///
+    /// Returns `ScriptStatus::Running` while a script runs, otherwise `ScriptStatus::Stopped`.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    /// - `InvalidScriptStatus` if Maestro reports a value other than 0 or 1
+    pub fn get_script_status(&mut self) -> Result<ScriptStatus, MaestroError> {
+        let res = self.send_command_u8(&[0xAE])?;
+        match res {
+            0 => Ok(ScriptStatus::Running),
+            1 => Ok(ScriptStatus::Stopped),
+            _ => Err(MaestroError::InvalidScriptStatus)
+        }
+    }
 
-fn convert_int_to_deg(i: i32) -> f64 {
-    return (i as f64 - 1984f64) / 44.44;
-}
+    /// Set the accelerations of all channels in vector.
+    ///
+    /// `channels` should be a vector of valid channels below the board's channel count.
+    /// # Errors:
+    /// - `LengthMismatch` if `channels` and `accelerations` have different lengths
+    /// - `InvalidChannel` if channel is out of range
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_accelerations(&mut self, channels: Vec<u8>, accelerations: Vec<u8>) -> Result<(), MaestroError> {
+        verify_lengths(channels.len(), accelerations.len())?;
+        for (channel, accel) in channels.into_iter().zip(accelerations) {
+            self.set_acceleration_raw(channel, accel)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the speeds of all channels in vector.
+    ///
+    /// `channels` should be a vector of valid channels below the board's channel count.
+    /// # Errors:
+    /// - `LengthMismatch` if `channels` and `speeds` have different lengths
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if a speed is greater than `SPEED_MAX`
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_speeds(&mut self, channels: Vec<u8>, speeds: Vec<u16>) -> Result<(), MaestroError> {
+        verify_lengths(channels.len(), speeds.len())?;
+        for (channel, speed) in channels.into_iter().zip(speeds) {
+            self.set_speed_raw(channel, speed)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the same acceleration on every listed channel.
+    ///
+    /// All Set Acceleration commands are sent in a single write. Nothing is sent if any channel or the
+    /// acceleration is invalid.
+    ///
+    /// `channels` should be valid channels below the board's channel count.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_uniform_acceleration(&mut self, channels: &[u8], acceleration: u8) -> Result<(), MaestroError> {
+        self.send_uniform(0x89, channels, acceleration.into())
+    }
+
+    /// Sets the same speed on every listed channel.
+    ///
+    /// All Set Speed commands are sent in a single write. Nothing is sent if any channel or the speed
+    /// is invalid.
+    ///
+    /// `channels` should be valid channels below the board's channel count.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if speed is greater than `SPEED_MAX`
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_uniform_speed(&mut self, channels: &[u8], speed: u16) -> Result<(), MaestroError> {
+        if speed > SPEED_MAX { return Err(MaestroError::OutOfBounds) }
+        self.send_uniform(0x87, channels, speed)
+    }
+
+    /// Sets the positions of all channels in vector.
+    ///
+    /// All Set Target commands are sent in a single write. Nothing is sent if any channel or position is invalid.
+    ///
+    /// `channels` should be a vector of valid channels below the board's channel count.
+    /// `positions` should be a degree 0 <= x <= 180
+    /// # Errors:
+    /// - `LengthMismatch` if `channels` and `positions` have different lengths
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if a position is outside 0 to 180 degrees
+    /// - `LimitExceeded` if a position is outside its channel's limits and violations are errors
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn set_positions(&mut self, channels: Vec<u8>, positions: Vec<f64>) -> Result<(), MaestroError> {
+        verify_lengths(channels.len(), positions.len())?;
+        let mut data = Vec::with_capacity(channels.len() * 4);
+        for (channel, pos) in channels.into_iter().zip(positions) {
+            verify_channel_range(channel, self.channel_count)?;
+            let target = self.apply_limits(channel, degrees_to_quarter_us(pos)?)?;
+            data.extend(self.frame(&form_data(0x84, channel, target)?));
+        }
+        if data.is_empty() { return Ok(()) }
+        self.transmit(&data, &mut [])
+    }
+
+    /// Gets the raw position of a single channel as the median of several reads.
+    ///
+    /// A single corrupted read is discarded as long as most reads agree. The requests are sent in one
+    /// write, but each sample still costs a two-byte response on the serial line, so latency grows
+    /// linearly with `samples` (about 2ms per sample at 9600 baud).
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `samples` should be at least 1; odd counts avoid ties.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if samples is 0
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn get_position_robust(&mut self, channel: u8, samples: u8) -> Result<u16, MaestroError> {
+        if samples == 0 { return Err(MaestroError::OutOfBounds) }
+        let mut positions = self.read_positions_raw(&vec![channel; samples as usize])?;
+        positions.sort_unstable();
+        Ok(positions[positions.len() / 2])
+    }
+
+    /// Gets the positions of all channels in vector.
+    ///
+    /// The requests for every channel are sent in a single write before any response is read. This
+    /// relies on the Maestro answering requests in the order it received them, which it always does
+    /// on a single serial line.
+    ///
+    /// `channels` should be a vector of valid channels below the board's channel count.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `ChannelDisabled` if a channel has no target (its output is off)
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn get_pos_motors(&mut self, channels: Vec<u8>) -> Result<Vec<f64>, MaestroError> {
+        self.read_positions_raw(&channels)?
+            .into_iter()
+            .map(|pos| if pos == 0 { Err(MaestroError::ChannelDisabled) } else { Ok(quarter_us_to_degrees(pos)) })
+            .collect()
+    }
+
+    /// Gets the raw positions of every channel on the board, in channel order.
+    ///
+    /// Positions are in quarter-microseconds; disabled channels report 0. The requests are sent in a
+    /// single write, as with `get_pos_motors`.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn get_all_positions(&mut self) -> Result<Vec<u16>, MaestroError> {
+        let channels: Vec<u8> = (0..self.channel_count).collect();
+        self.read_positions_raw(&channels)
+    }
+
+    /// Reads the raw positions of several channels, pipelining the requests.
+    ///
+    /// The requests and all of their responses are a single exchange, so reconnection and error
+    /// reporting apply to the whole batch. The response may take the timeout once per channel.
+    fn read_positions_raw(&mut self, channels: &[u8]) -> Result<Vec<u16>, MaestroError> {
+        let mut data = Vec::with_capacity(channels.len() * 2);
+        for &channel in channels {
+            verify_channel_range(channel, self.channel_count)?;
+            data.extend(self.frame(&[0x90, channel]));
+        }
+        if data.is_empty() { return Ok(Vec::new()) }
+        let mut response = vec![0u8; 2 * channels.len()];
+        self.transmit_pipelined(&data, &mut response, channels.len() as u32)?;
+        Ok(response.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect())
+    }
+
+    /// Check if any of the servos are currently moving.
+    ///
+    /// Returns `MovingState::ServosMoving` if any servos are currently moving, otherwise returning `MovingState::ServoStopped`.
+    ///
+    /// # Error
+    /// - `UnableToSend` if serialport was unable to send the command to Maestro.
+    pub fn get_moving_state(&mut self) -> Result<MovingState, MaestroError> {
+        let res = self.send_command_u8(&[0x93])?;
+        match res {
+            0 => Ok(MovingState::ServosStopped),
+            1 => Ok(MovingState::ServosMoving),
+            _ => Err(MaestroError::InvalidMovingState)
+        }
+    }
+
+    /// Check if any of the servos are currently moving.
+    ///
+    /// Returns `true` if any servos are still moving, otherwise `false`.
+    ///
+    /// # Error
+    /// - `UnableToSend` if serialport was unable to send the command to Maestro.
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    /// - `InvalidMovingState` if Maestro reports a value other than 0 or 1
+    pub fn is_moving(&mut self) -> Result<bool, MaestroError> {
+        Ok(matches!(self.get_moving_state()?, MovingState::ServosMoving))
+    }
+
+    /// Blocks until all servos have stopped moving.
+    ///
+    /// Polls `get_moving_state` every `poll_interval`. If `timeout` is given and elapses before the
+    /// servos stop, `MoveTimeout` is returned.
+    /// # Errors:
+    /// - `MoveTimeout` if the servos were still moving when `timeout` elapsed
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn wait_until_stopped(&mut self, poll_interval: Duration, timeout: Option<Duration>) -> Result<(), MaestroError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        while self.is_moving()? {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(MaestroError::MoveTimeout);
+            }
+            thread::sleep(poll_interval);
+        }
+        Ok(())
+    }
+
+    /// Steps a single channel from one angle to another, reading back the position at each step.
+    ///
+    /// Each step is commanded with `set_position`, so the channel's limits apply. After `dwell` the
+    /// position is read and recorded with the commanded angle. The sweep runs toward `to_deg` in
+    /// either direction and always ends exactly at `to_deg`. Steps never leave the range between
+    /// `from_deg` and `to_deg`, and a step that rounding leaves within a hair of `to_deg` is replaced
+    /// by `to_deg` itself.
+    ///
+    /// Angles are `f64` degrees, like `set_position`, rather than whole degrees, and each reading is
+    /// the raw position from `get_position_raw` in quarter-microseconds.
+    ///
+    /// `channel` should be a valid channel below the board's channel count.
+    /// `from_deg` and `to_deg` should be degrees 0 <= x <= 180 and `step_deg` greater than 0.
+    /// # Errors:
+    /// - `InvalidChannel` if channel is out of range
+    /// - `OutOfBounds` if an angle is outside 0 to 180 or the step is not positive
+    /// - `LimitExceeded` if a step is outside the channel's limits and violations are errors
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn sweep(&mut self, channel: u8, from_deg: f64, to_deg: f64, step_deg: f64, dwell: Duration) -> Result<Vec<(f64, u16)>, MaestroError> {
+        verify_channel_range(channel, self.channel_count)?;
+        degrees_to_quarter_us(from_deg)?;
+        degrees_to_quarter_us(to_deg)?;
+        if !(step_deg > 0.0 && step_deg.is_finite()) { return Err(MaestroError::OutOfBounds) }
+        let direction = if to_deg < from_deg { -1.0 } else { 1.0 };
+        let steps = ((to_deg - from_deg).abs() / step_deg).floor() as usize;
+        let (low, high) = if from_deg < to_deg { (from_deg, to_deg) } else { (to_deg, from_deg) };
+        let mut angles: Vec<f64> = (0..=steps)
+            .map(|i| (from_deg + direction * step_deg * i as f64).clamp(low, high))
+            .collect();
+        if angles.len() > 1 && angles.last().is_some_and(|&last| (last - to_deg).abs() < SWEEP_EPSILON) {
+            angles.pop();
+        }
+        if angles.last() != Some(&to_deg) {
+            angles.push(to_deg);
+        }
+        let mut readings = Vec::with_capacity(angles.len());
+        for degree in angles {
+            self.set_position(channel, degree)?;
+            thread::sleep(dwell);
+            readings.push((degree, self.get_position_raw(channel)?));
+        }
+        Ok(readings)
+    }
+
+    /// Gets the error flags currently raised on the Maestro.
+    ///
+    /// Reading the errors clears them on the board.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    pub fn get_errors(&mut self) -> Result<MaestroErrorFlags, MaestroError> {
+        let res = self.send_command_u16(&[0xA1])?;
+        Ok(MaestroErrorFlags::from_raw(res))
+    }
+
+    /// Sends an arbitrary Compact protocol command that has no response.
+    ///
+    /// This is an unchecked escape hatch for commands this crate does not wrap yet. `bytes` is the
+    /// command byte followed by its data; nothing is validated. The device number prefix and CRC byte
+    /// are still added when configured.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    pub fn send_raw(&mut self, bytes: &[u8]) -> Result<(), MaestroError> {
+        self.send_command_no_response(bytes)
+    }
+
+    /// Sends an arbitrary Compact protocol command and reads `response_len` bytes back.
+    ///
+    /// Like `send_raw`, this is unchecked. If `response_len` does not match what the Maestro actually
+    /// sends, the leftover or missing bytes will corrupt the responses of later commands.
+    /// # Errors:
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro does not send back `response_len` bytes
+    pub fn send_raw_expect(&mut self, bytes: &[u8], response_len: usize) -> Result<Vec<u8>, MaestroError> {
+        let frame = self.frame(bytes);
+        let mut buf = vec![0u8; response_len];
+        self.transmit(&frame, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Reads the positions of the given channels, the moving state and the error flags together.
+    ///
+    /// Positions are raw quarter-microsecond values in the order of `channels`; disabled channels
+    /// report 0. Reading the errors clears them on the board, as with `get_errors`.
+    ///
+    /// `channels` should be valid channels below the board's channel count.
+    /// # Errors:
+    /// - `InvalidChannel` if a channel is out of range, in which case nothing is sent
+    /// - `UnableToSend` if serial port was unable to send command to Maestro
+    /// - `UnableToReceive` if Maestro sends back invalid data
+    /// - `InvalidMovingState` if Maestro sends back an invalid moving state
+    pub fn status_snapshot(&mut self, channels: &[u8]) -> Result<BoardStatus, MaestroError> {
+        let positions = self.read_positions_raw(channels)?;
+        let moving = self.get_moving_state()?;
+        let errors = self.get_errors()?;
+        Ok(BoardStatus { positions, moving, errors })
+    }
+
+    /// Sends one command with the same value to every listed channel in a single write.
+    fn send_uniform(&mut self, command: u8, channels: &[u8], value: u16) -> Result<(), MaestroError> {
+        let mut data = Vec::with_capacity(channels.len() * 4);
+        for &channel in channels {
+            verify_channel_range(channel, self.channel_count)?;
+            data.extend(self.frame(&form_data(command, channel, value)?));
+        }
+        if data.is_empty() { return Ok(()) }
+        self.transmit(&data, &mut [])
+    }
+
+    fn apply_limits(&self, channel: u8, target: u16) -> Result<u16, MaestroError> {
+        match self.limits.get(&channel) {
+            Some(&(min, max)) if target != 0 && !(min..=max).contains(&target) => match self.limit_violation {
+                LimitViolation::Clamp => Ok(target.clamp(min, max)),
+                LimitViolation::Error => Err(MaestroError::LimitExceeded),
+            },
+            _ => Ok(target)
+        }
+    }
+
+    /// Builds the bytes sent on the wire for a Compact protocol command.
+    fn frame(&self, data: &[u8]) -> Vec<u8> {
+        #[cfg(feature = "tracing")]
+        trace_command(data);
+        let mut frame = frame_command(self.device_number, data);
+        if self.crc_enabled {
+            frame.push(crc7(&frame));
+        }
+        frame
+    }
+
+    fn send_command_no_response(&mut self, data: &[u8]) -> Result<(), MaestroError> {
+        let frame = self.frame(data);
+        self.transmit(&frame, &mut [])
+    }
+
+    /// Sends a command whose response is a single byte.
+    fn send_command_u8(&mut self, data: &[u8]) -> Result<u8, MaestroError> {
+        let frame = self.frame(data);
+        let mut buf = [0u8; 1];
+        self.transmit(&frame, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    /// Sends a command whose response is a little-endian two-byte value.
+    fn send_command_u16(&mut self, data: &[u8]) -> Result<u16, MaestroError> {
+        let frame = self.frame(data);
+        let mut buf = [0u8; 2];
+        self.transmit(&frame, &mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    /// Sends `frame` and fills `response`, going through the command queue if there is one.
+    fn transmit(&mut self, frame: &[u8], response: &mut [u8]) -> Result<(), MaestroError> {
+        self.transmit_pipelined(frame, response, 1)
+    }
+
+    /// Like `transmit`, for a frame of `commands` pipelined commands that each answer within the timeout.
+    fn transmit_pipelined(&mut self, frame: &[u8], response: &mut [u8], commands: u32) -> Result<(), MaestroError> {
+        match &self.queue {
+            Some(queue) if response.is_empty() => return queue.push(frame),
+            Some(queue) => queue.wait_until_written()?,
+            None => {}
+        }
+        self.transmit_now(frame, response, commands)
+    }
+
+    /// Writes `frame` and fills `response`, reconnecting and retrying on failure if enabled.
+    ///
+    /// The response must arrive within the timeout once per command in `frame`.
+    fn transmit_now(&mut self, frame: &[u8], response: &mut [u8], commands: u32) -> Result<(), MaestroError> {
+        self.link.lock().unwrap().transmit(frame, response, commands)
+    }
+}
+
+/// The transport a `Maestro` talks over and how to reopen it, shared with its command queue's
+/// writer thread.
+struct Link {
+    transport: Box<dyn MaestroTransport>,
+    connection: Option<Connection>,
+    reconnect_retries: u8,
+    reconnect_delay: Duration
+}
+
+impl Link {
+    /// Writes `frame` and fills `response`, reconnecting and retrying on failure if enabled.
+    fn transmit(&mut self, frame: &[u8], response: &mut [u8], commands: u32) -> Result<(), MaestroError> {
+        let mut attempts = 0;
+        loop {
+            let res = self.write_frame(frame).and_then(|_| self.read_response(response, commands));
+            match res {
+                Err(MaestroError::UnableToSend | MaestroError::Disconnected)
+                    if attempts < self.reconnect_retries && self.connection.is_some() => {
+                    attempts += 1;
+                    thread::sleep(self.reconnect_delay);
+                    self.reconnect();
+                }
+                res => {
+                    #[cfg(feature = "tracing")]
+                    if let Err(e) = &res {
+                        tracing::warn!(error = %e, frame = ?frame, "Maestro command failed");
+                    }
+                    return res
+                }
+            }
+        }
+    }
+
+    /// Closes the serial port and opens it again with the original settings.
+    ///
+    /// If the port cannot be reopened the `Maestro` stays disconnected until a later reconnect succeeds.
+    fn reconnect(&mut self) {
+        let Some(connection) = &self.connection else { return };
+        let timeout = self.transport.timeout();
+        // Release the old handle first; ports are opened exclusively.
+        self.transport = Box::new(ClosedTransport::new(timeout));
+        if let Ok(serial_port) = serialport::new(&connection.port, connection.baud).timeout(timeout).open() {
+            self.transport = Box::new(serial_port);
+        }
+    }
+
+    /// Fills `buf` from the serial port, accumulating partial reads.
+    ///
+    /// The port's timeout, multiplied by the number of pipelined `commands` being answered, is treated
+    /// as a deadline for the whole response rather than for each read, so bytes that arrive before a
+    /// per-read timeout are kept.
+    fn read_response(&mut self, buf: &mut [u8], commands: u32) -> Result<(), MaestroError> {
+        let deadline = Instant::now() + self.transport.timeout() * commands;
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.transport.read(&mut buf[filled..]) {
+                // End of file means the other end closed the port.
+                Ok(0) => return Err(MaestroError::Disconnected),
+                Ok(n) => filled += n,
+                Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock | ErrorKind::Interrupted) => {
+                    if Instant::now() >= deadline {
+                        return Err(MaestroError::UnableToReceive);
+                    }
+                }
+                Err(e) if is_disconnect(e.kind()) => return Err(MaestroError::Disconnected),
+                Err(_) => return Err(MaestroError::UnableToReceive)
+            }
+        }
+        Ok(())
+    }
+
+    fn write_frame(&mut self, frame: &[u8]) -> Result<(), MaestroError> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(bytes = ?frame, "writing to Maestro");
+        match self.transport.write(frame) {
+            Ok(_) => Ok(()),
+            Err(e) if is_disconnect(e.kind()) => Err(MaestroError::Disconnected),
+            Err(_) => Err(MaestroError::UnableToSend)
+        }
+    }
+}
+
+/// Whether an I/O error means the port itself has gone away rather than a single transfer failing.
+fn is_disconnect(kind: ErrorKind) -> bool {
+    matches!(kind, ErrorKind::BrokenPipe | ErrorKind::NotConnected | ErrorKind::UnexpectedEof)
+}
+
+/// Settings needed to reopen the serial port a `Maestro` was created from.
+pub(crate) struct Connection {
+    pub(crate) port: String,
+    pub(crate) baud: u32
+}
+
+/// Commands waiting on the host for a background thread to write them once the serial line is free.
+struct CommandQueue {
+    shared: Arc<(Mutex<QueueState>, Condvar)>,
+    writer: Option<JoinHandle<()>>
+}
+
+/// State shared between a `CommandQueue` and its writer thread.
+#[derive(Default)]
+struct QueueState {
+    pending: Vec<u8>,
+    writing: bool,
+    error: Option<MaestroError>,
+    shutdown: bool,
+    /// Set once the writer thread has exited, even by panicking.
+    stopped: bool
+}
+
+/// Marks the writer thread as stopped when it exits, waking anyone waiting on it.
+struct WriterExit(Arc<(Mutex<QueueState>, Condvar)>);
+
+impl Drop for WriterExit {
+    fn drop(&mut self) {
+        let (state, changed) = &*self.0;
+        state.lock().unwrap_or_else(PoisonError::into_inner).stopped = true;
+        changed.notify_all();
+    }
+}
+
+impl CommandQueue {
+    fn new(link: Arc<Mutex<Link>>, bytes_per_second: u32) -> Self {
+        let shared = Arc::new((Mutex::new(QueueState::default()), Condvar::new()));
+        let writer_shared = shared.clone();
+        let writer = thread::spawn(move || {
+            let _exit = WriterExit(writer_shared.clone());
+            let (state, changed) = &*writer_shared;
+            let mut guard = state.lock().unwrap();
+            loop {
+                if guard.shutdown { return }
+                if guard.pending.is_empty() {
+                    guard = changed.wait(guard).unwrap();
+                    continue;
+                }
+                let bytes = std::mem::take(&mut guard.pending);
+                guard.writing = true;
+                drop(guard);
+                let res = link.lock().unwrap().transmit(&bytes, &mut [], 1);
+                guard = state.lock().unwrap();
+                guard.writing = false;
+                if let Err(e) = res {
+                    guard.error = Some(e);
+                }
+                changed.notify_all();
+                drop(guard);
+                // Let the line carry what was just written before sending more.
+                thread::sleep(Duration::from_secs_f64(bytes.len() as f64 / bytes_per_second as f64));
+                guard = state.lock().unwrap();
+            }
+        });
+        CommandQueue { shared, writer: Some(writer) }
+    }
+
+    /// Queues `frame` for the writer thread, first returning any error from an earlier write.
+    /// # Errors:
+    /// - `UnableToSend` if the writer thread has stopped
+    fn push(&self, frame: &[u8]) -> Result<(), MaestroError> {
+        let (state, changed) = &*self.shared;
+        let mut guard = state.lock().unwrap();
+        if let Some(e) = guard.error.take() { return Err(e) }
+        if guard.stopped { return Err(MaestroError::UnableToSend) }
+        guard.pending.extend_from_slice(frame);
+        changed.notify_all();
+        Ok(())
+    }
+
+    /// Blocks until the writer thread has written everything queued.
+    /// # Errors:
+    /// - `UnableToSend` if the writer thread stopped before everything was written
+    fn wait_until_written(&self) -> Result<(), MaestroError> {
+        let (state, changed) = &*self.shared;
+        let mut guard = state.lock().unwrap();
+        while (!guard.pending.is_empty() || guard.writing) && !guard.stopped {
+            guard = changed.wait(guard).unwrap();
+        }
+        if let Some(e) = guard.error.take() { return Err(e) }
+        if guard.stopped && (!guard.pending.is_empty() || guard.writing) { return Err(MaestroError::UnableToSend) }
+        Ok(())
+    }
+
+    /// Discards everything not yet handed to the writer thread.
+    fn clear(&self) {
+        self.shared.0.lock().unwrap().pending.clear();
+    }
+}
+
+impl Drop for CommandQueue {
+    fn drop(&mut self) {
+        let (state, changed) = &*self.shared;
+        state.lock().unwrap().shutdown = true;
+        changed.notify_all();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl Drop for Maestro {
+    fn drop(&mut self) {
+        let _ = match self.drop_behavior {
+            DropBehavior::Hold => Ok(()),
+            DropBehavior::Home => self.go_home(),
+            DropBehavior::Disable => {
+                let channels: Vec<u8> = (0..self.channel_count).collect();
+                self.disable_all(&channels)
+            }
+        };
+        let _ = self.flush();
+    }
+}
+
+/// What a `Maestro` does with the servos when it is dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropBehavior {
+    /// Leave the servos holding their last target
+    Hold,
+    /// Send every servo to its configured home position
+    Home,
+    /// Set every target to 0, turning the servo outputs off
+    Disable
+}
+
+/// How a target outside a channel's limits is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitViolation {
+    /// Move to the nearest limit instead
+    Clamp,
+    /// Reject the command with `LimitExceeded`
+    Error
+}
+
+/// Lists the names of the serial ports available on this system.
+///
+/// Any of the returned names can be passed to `Maestro::new`.
+///
+/// # Example:
+/// ```
+/// use maestro_control::available_ports;
+///
+/// if let Ok(ports) = available_ports() {
+///     for port in ports {
+///         println!("{}", port);
+///     }
+/// }
+/// ```
+/// # Errors:
+/// - `PortEnumerationFailed` if the system's serial ports could not be listed
+pub fn available_ports() -> Result<Vec<String>, MaestroError> {
+    let ports = serialport::available_ports().map_err(|_| MaestroError::PortEnumerationFailed)?;
+    Ok(ports.into_iter().map(|port| port.port_name).collect())
+}
+
+fn find_device_info(port: &str, ports: Vec<SerialPortInfo>) -> Option<DeviceInfo> {
+    ports.into_iter().find(|info| info.port_name == port).and_then(|info| match info.port_type {
+        SerialPortType::UsbPort(usb) => Some(DeviceInfo {
+            vid: usb.vid,
+            pid: usb.pid,
+            serial_number: usb.serial_number,
+            manufacturer: usb.manufacturer,
+            product: usb.product,
+        }),
+        _ => None
+    })
+}
+
+/// Returned enum based on current servo status.
+///
+/// Displays as `moving` or `stopped`.
+///
+/// # Example:
+/// ```
+/// use maestro_control::Maestro;
+///
+/// match Maestro::new("COM1") {
+///     Ok(mut maestro) => match maestro.get_moving_state() {
+///         Ok(state) => println!("Servos are {}", state),
+///         Err(e) => println!("Unable to read moving state: {}", e),
+///     },
+///     Err(e) => println!("Unable to open Maestro: {}", e),
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovingState {
+    /// Is returned if servos are still moving
+    ServosMoving,
+    /// Is returned if all servos have stopped moving
+    ServosStopped
+}
+
+impl fmt::Display for MovingState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MovingState::ServosMoving => write!(f, "moving"),
+            MovingState::ServosStopped => write!(f, "stopped"),
+        }
+    }
+}
+
+/// Snapshot of a Maestro's state returned by `Maestro::status_snapshot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardStatus {
+    /// Raw positions in quarter-microseconds, in the order the channels were requested
+    pub positions: Vec<u16>,
+    /// Whether any servo is still moving
+    pub moving: MovingState,
+    /// Error flags that were raised on the board
+    pub errors: MaestroErrorFlags
+}
+
+/// USB details of the port a Maestro is connected to, returned by `Maestro::device_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// USB vendor ID
+    pub vid: u16,
+    /// USB product ID
+    pub pid: u16,
+    /// Serial number of the board, if reported
+    pub serial_number: Option<String>,
+    /// Manufacturer name, if reported
+    pub manufacturer: Option<String>,
+    /// Product name, if reported
+    pub product: Option<String>
+}
+
+/// Whether the Maestro's script is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptStatus {
+    /// Is returned if a script is running
+    Running,
+    /// Is returned if no script is running
+    Stopped
+}
+
+fn form_data(command: u8, channel: u8, data:u16) -> Result<[u8; 4], MaestroError> {
+    protocol::form_data(command, channel, data).ok_or(MaestroError::OutOfBounds)
+}
+
+/// Splits a value into the low and high 7-bit data bytes used by the protocol.
+///
+/// Values that do not fit in 14 bits are rejected rather than truncated.
+fn split_data(data: u16) -> Result<[u8; 2], MaestroError> {
+    protocol::split_data(data).ok_or(MaestroError::OutOfBounds)
+}
+
+/// Converts a Compact protocol command into the frame sent on the wire.
+///
+/// With a device number the Pololu protocol is used: the frame is prefixed with `0xAA` and the
+/// device number, and the command byte has its most significant bit cleared.
+fn frame_command(device_number: Option<u8>, data: &[u8]) -> Vec<u8> {
+    match (device_number, data.split_first()) {
+        (Some(device), Some((command, rest))) => {
+            let mut frame = Vec::with_capacity(data.len() + 2);
+            frame.extend(protocol::pololu_header(device, *command));
+            frame.extend_from_slice(rest);
+            frame
+        }
+        _ => data.to_vec()
+    }
+}
+
+/// Logs a Compact protocol command at trace level.
+#[cfg(feature = "tracing")]
+fn trace_command(data: &[u8]) {
+    let Some((&opcode, payload)) = data.split_first() else { return };
+    match (opcode, payload.first()) {
+        (0x84 | 0x87 | 0x89 | 0x90, Some(&channel)) => {
+            tracing::trace!(opcode, channel, payload = ?&payload[1..], "sending Maestro command");
+        }
+        _ => tracing::trace!(opcode, payload = ?payload, "sending Maestro command"),
+    }
+}
+
+fn verify_channel_range(channel: u8, channel_count: u8) -> Result<(), MaestroError> {
+    if channel >= channel_count {
+        Err(MaestroError::InvalidChannel)
+    } else {
+        Ok(())
+    }
+}
+
+fn verify_subroutine(subroutine: u8) -> Result<(), MaestroError> {
+    if subroutine > 0x7F {
+        Err(MaestroError::OutOfBounds)
+    } else {
+        Ok(())
+    }
+}
+
+fn verify_lengths(channels: usize, values: usize) -> Result<(), MaestroError> {
+    if channels != values {
+        Err(MaestroError::LengthMismatch)
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+
+    #[test]
+    fn set_timeout_propagates_to_port() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_timeout(Duration::from_millis(250)).unwrap();
+        assert_eq!(transport.timeout(), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn go_home_sends_single_byte() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.go_home().unwrap();
+        assert_eq!(transport.written(), vec![0xA2]);
+    }
+
+    #[test]
+    fn set_speed_uses_speed_command() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_speed_raw(2, 140).unwrap();
+        assert_eq!(transport.written(), vec![0x87, 2, 0x0C, 0x01]);
+    }
+
+    #[test]
+    fn set_acceleration_uses_acceleration_command() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_acceleration_raw(2, 4).unwrap();
+        assert_eq!(transport.written(), vec![0x89, 2, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn set_position_mini_ssc_frame() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_position_mini_ssc(5, 127).unwrap();
+        assert_eq!(transport.written(), vec![0xFF, 5, 127]);
+        assert!(matches!(maestro.set_position_mini_ssc(5, 255), Err(MaestroError::OutOfBounds)));
+    }
+
+    #[test]
+    fn device_number_prefixes_pololu_protocol() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.device_number = Some(12);
+        maestro.set_speed_raw(1, 20).unwrap();
+        maestro.go_home().unwrap();
+        assert_eq!(transport.writes(), vec![vec![0xAA, 12, 0x07, 1, 20, 0], vec![0xAA, 12, 0x22]]);
+    }
+
+    #[test]
+    fn get_position_reads_raw_and_degrees() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[0x70, 0x17]);
+        assert_eq!(maestro.get_position_raw(4).unwrap(), 6000);
+        assert_eq!(transport.written(), vec![0x90, 4]);
+        transport.queue_response(&[0xC0, 0x07]);
+        assert_eq!(maestro.get_position(4).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn get_position_of_disabled_channel() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[0, 0]);
+        assert!(matches!(maestro.get_position(4), Err(MaestroError::ChannelDisabled)));
+    }
+
+    #[test]
+    fn batch_setters_reject_length_mismatch() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert!(matches!(maestro.set_positions(vec![0, 1, 2], vec![90.0, 90.0]), Err(MaestroError::LengthMismatch)));
+        assert!(matches!(maestro.set_speeds(vec![0, 1], vec![10]), Err(MaestroError::LengthMismatch)));
+        assert!(matches!(maestro.set_accelerations(vec![0], vec![1, 2]), Err(MaestroError::LengthMismatch)));
+        assert!(transport.written().is_empty());
+    }
+
+    #[test]
+    fn drop_holds_by_default() {
+        let transport = MemoryTransport::new();
+        drop(Maestro::from_transport(transport.clone()));
+        assert!(transport.written().is_empty());
+    }
+
+    #[test]
+    fn drop_sends_configured_behavior() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_drop_behavior(DropBehavior::Home);
+        drop(maestro);
+        assert_eq!(transport.written(), vec![0xA2]);
+
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_drop_behavior(DropBehavior::Disable);
+        drop(maestro);
+        let expected: Vec<u8> = (0..12).flat_map(|channel| [0x84, channel, 0, 0]).collect();
+        assert_eq!(transport.writes(), vec![expected]);
+    }
+
+    #[test]
+    fn set_target_sends_raw_value() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_target(3, 6000).unwrap();
+        assert_eq!(transport.written(), vec![0x84, 3, 0x70, 0x2E]);
+        assert!(matches!(maestro.set_target(12, 6000), Err(MaestroError::InvalidChannel)));
+    }
+
+    #[test]
+    fn send_command_u16_accumulates_partial_reads() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.set_max_read(1);
+        transport.queue_response(&[0x70, 0x17]);
+        assert_eq!(maestro.get_position_raw(0).unwrap(), 6000);
+    }
+
+    #[test]
+    fn send_command_u16_incomplete_response() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_timeout(Duration::from_millis(5)).unwrap();
+        transport.queue_response(&[0x70]);
+        assert!(matches!(maestro.get_position_raw(0), Err(MaestroError::UnableToReceive)));
+    }
+
+    #[test]
+    fn channel_count_limits_channels() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.channel_count = 18;
+        assert!(maestro.set_target(17, 6000).is_ok());
+        assert!(matches!(maestro.set_target(18, 6000), Err(MaestroError::InvalidChannel)));
+        assert!(maestro.set_multiple_targets(12, &[6000; 6]).is_ok());
+        assert!(matches!(maestro.set_multiple_targets(12, &[6000; 7]), Err(MaestroError::InvalidChannel)));
+    }
+
+    #[test]
+    fn is_moving_maps_state() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[1]);
+        assert!(maestro.is_moving().unwrap());
+        transport.queue_response(&[0]);
+        assert!(!maestro.is_moving().unwrap());
+        assert_eq!(transport.written(), vec![0x93, 0x93]);
+    }
+
+    #[test]
+    fn wait_until_stopped_polls_until_stopped() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[1, 1, 0]);
+        maestro.wait_until_stopped(Duration::from_millis(1), None).unwrap();
+        assert_eq!(transport.written(), vec![0x93, 0x93, 0x93]);
+    }
+
+    #[test]
+    fn wait_until_stopped_times_out() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[1; 100]);
+        let res = maestro.wait_until_stopped(Duration::from_millis(1), Some(Duration::from_millis(5)));
+        assert!(matches!(res, Err(MaestroError::MoveTimeout)));
+    }
+
+    #[test]
+    fn channel_limits_reject_by_default() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_channel_limits(1, 45.0, 135.0).unwrap();
+        assert!(matches!(maestro.set_position(1, 150.0), Err(MaestroError::LimitExceeded)));
+        assert!(matches!(maestro.set_multiple_targets(0, &[6000, 1984]), Err(MaestroError::LimitExceeded)));
+        assert!(maestro.set_position(1, 90.0).is_ok());
+        assert!(maestro.set_target(1, 0).is_ok());
+        assert!(maestro.set_position(2, 150.0).is_ok());
+    }
+
+    #[test]
+    fn channel_limits_clamp() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_channel_limits(1, 45.0, 135.0).unwrap();
+        maestro.set_limit_violation(LimitViolation::Clamp);
+        maestro.set_position(1, 10.0).unwrap();
+        let min = degrees_to_quarter_us(45.0).unwrap();
+        assert_eq!(transport.written(), form_data(0x84, 1, min).unwrap().to_vec());
+    }
+
+    #[test]
+    fn channel_limits_validate_range() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert!(matches!(maestro.set_channel_limits(1, 90.0, 45.0), Err(MaestroError::OutOfBounds)));
+        assert!(matches!(maestro.set_channel_limits(1, 0.0, 200.0), Err(MaestroError::OutOfBounds)));
+        assert!(matches!(maestro.set_channel_limits(12, 0.0, 90.0), Err(MaestroError::InvalidChannel)));
+    }
+
+    #[test]
+    fn set_positions_single_write() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_positions(vec![0, 1, 2], vec![0.0, 90.0, 180.0]).unwrap();
+        let writes = transport.writes();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].len(), 12);
+        assert_eq!(writes[0][..4], form_data(0x84, 0, degrees_to_quarter_us(0.0).unwrap()).unwrap());
+        assert_eq!(writes[0][8..], form_data(0x84, 2, degrees_to_quarter_us(180.0).unwrap()).unwrap());
+        assert!(matches!(maestro.set_positions(vec![0, 12], vec![0.0, 90.0]), Err(MaestroError::InvalidChannel)));
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn moving_state_display() {
+        assert_eq!(MovingState::ServosMoving.to_string(), "moving");
+        assert_eq!(MovingState::ServosStopped.to_string(), "stopped");
+    }
+
+    #[test]
+    fn set_pwm_frame() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_pwm(1000, 4800).unwrap();
+        assert_eq!(transport.written(), vec![0x8A, 0x68, 0x07, 0x40, 0x25]);
+    }
+
+    #[test]
+    fn script_control_frames() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.stop_script().unwrap();
+        maestro.restart_script_at(3).unwrap();
+        maestro.restart_script_at_with_param(4, 300).unwrap();
+        assert_eq!(transport.writes(), vec![vec![0xA4], vec![0xA7, 3], vec![0xA8, 4, 0x2C, 0x02]]);
+        assert!(matches!(maestro.restart_script_at(128), Err(MaestroError::OutOfBounds)));
+    }
+
+    #[test]
+    fn get_script_status_reads_one_byte() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[0, 1, 2]);
+        assert_eq!(maestro.get_script_status().unwrap(), ScriptStatus::Running);
+        assert_eq!(maestro.get_script_status().unwrap(), ScriptStatus::Stopped);
+        assert!(matches!(maestro.get_script_status(), Err(MaestroError::InvalidScriptStatus)));
+        assert_eq!(transport.written(), vec![0xAE, 0xAE, 0xAE]);
+    }
+
+    #[test]
+    fn get_moving_state_reads_one_byte() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[1, 0]);
+        assert_eq!(maestro.get_moving_state().unwrap(), MovingState::ServosMoving);
+        assert_eq!(maestro.get_moving_state().unwrap(), MovingState::ServosStopped);
+    }
+
+    #[test]
+    fn crc_appended_when_enabled() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.enable_crc(true);
+        maestro.set_target(0, 6000).unwrap();
+        maestro.set_positions(vec![0, 1], vec![90.0, 90.0]).unwrap();
+        let writes = transport.writes();
+        assert_eq!(writes[0], vec![0x84, 0, 0x70, 0x2E, 0x2B]);
+        assert_eq!(writes[1].len(), 10);
+        assert_eq!(writes[1][4], crc7(&writes[1][..4]));
+        assert_eq!(writes[1][9], crc7(&writes[1][5..9]));
+    }
+
+    #[test]
+    fn reconnect_requires_serial_connection() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_auto_reconnect(3);
+        transport.set_write_error(Some(ErrorKind::BrokenPipe));
+        assert!(matches!(maestro.go_home(), Err(MaestroError::Disconnected)));
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn reconnect_gives_up_after_retries() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_connection(Connection { port: "COM0".to_string(), baud: 9600 });
+        maestro.set_auto_reconnect(3);
+        maestro.set_reconnect_delay(Duration::from_millis(5));
+        transport.set_write_error(Some(ErrorKind::BrokenPipe));
+        let start = Instant::now();
+        assert!(matches!(maestro.go_home(), Err(MaestroError::Disconnected)));
+        assert!(start.elapsed() >= Duration::from_millis(15));
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn queued_commands_reconnect_on_failure() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_connection(Connection { port: "COM0".to_string(), baud: 9600 });
+        maestro.set_auto_reconnect(3);
+        maestro.set_reconnect_delay(Duration::from_millis(5));
+        maestro.set_command_queue(Some(1000)).unwrap();
+        transport.set_write_error(Some(ErrorKind::BrokenPipe));
+        let start = Instant::now();
+        maestro.go_home().unwrap();
+        assert!(matches!(maestro.flush(), Err(MaestroError::Disconnected)));
+        assert!(start.elapsed() >= Duration::from_millis(15));
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn disable_all_sends_zero_targets() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_channel_limits(1, 45.0, 135.0).unwrap();
+        maestro.disable_all(&[0, 1]).unwrap();
+        assert_eq!(transport.writes(), vec![vec![0x84, 0, 0, 0, 0x84, 1, 0, 0]]);
+        assert!(matches!(maestro.disable_all(&[0, 12]), Err(MaestroError::InvalidChannel)));
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn get_pos_motors_pipelines_requests() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[0xC0, 0x07, 0x60, 0x17, 0x00, 0x27]);
+        let positions = maestro.get_pos_motors(vec![0, 1, 2]).unwrap();
+        assert_eq!(transport.writes(), vec![vec![0x90, 0, 0x90, 1, 0x90, 2]]);
+        assert_eq!(positions, vec![0.0, 90.0, 180.0]);
+    }
+
+    #[test]
+    fn write_to_unplugged_port_reports_disconnected() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.set_write_error(Some(ErrorKind::NotConnected));
+        assert!(matches!(maestro.set_target(0, 6000), Err(MaestroError::Disconnected)));
+        transport.set_write_error(Some(ErrorKind::Other));
+        assert!(matches!(maestro.set_target(0, 6000), Err(MaestroError::UnableToSend)));
+    }
+
+    #[test]
+    fn move_with_profile_sends_profile_before_target() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.move_with_profile(2, 90.0, 60, 4).unwrap();
+        assert_eq!(transport.writes(), vec![vec![0x89, 2, 4, 0, 0x87, 2, 60, 0, 0x84, 2, 0x60, 0x2E]]);
+        assert!(matches!(maestro.move_with_profile(2, 90.0, SPEED_MAX + 1, 4), Err(MaestroError::OutOfBounds)));
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn send_raw_frames_command() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.send_raw(&[0xA2]).unwrap();
+        transport.queue_response(&[0x01, 0x02, 0x03]);
+        assert_eq!(maestro.send_raw_expect(&[0xB0, 5], 3).unwrap(), vec![1, 2, 3]);
+        assert_eq!(transport.writes(), vec![vec![0xA2], vec![0xB0, 5]]);
+    }
+
+    #[test]
+    fn status_snapshot_reads_positions_moving_state_and_errors() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[0x70, 0x17, 0x00, 0x00, 0x01, 0x02, 0x00]);
+        let status = maestro.status_snapshot(&[0, 3]).unwrap();
+        assert_eq!(status.positions, vec![6000, 0]);
+        assert_eq!(status.moving, MovingState::ServosMoving);
+        assert_eq!(status.errors.raw(), 0x0002);
+        assert_eq!(transport.written(), vec![0x90, 0, 0x90, 3, 0x93, 0xA1]);
+    }
+
+    #[test]
+    fn channel_checks_board_channel_count() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        let channel = maestro.channel(11).unwrap();
+        maestro.set_target_on(channel, 6000).unwrap();
+        assert_eq!(transport.written(), vec![0x84, 11, 0x70, 0x2E]);
+        assert_eq!(maestro.channel(12), Err(MaestroError::InvalidChannel));
+        let channel = Channel::new(20).unwrap();
+        assert!(matches!(maestro.set_target_on(channel, 6000), Err(MaestroError::InvalidChannel)));
+    }
+
+    #[test]
+    fn home_channel_stores_and_sends_home() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert_eq!(maestro.rehome_channel(1), Err(MaestroError::HomeNotSet));
+        maestro.home_channel(1, 90).unwrap();
+        maestro.home_channel(1, 90).unwrap();
+        maestro.rehome_channel(1).unwrap();
+        assert_eq!(transport.writes(), vec![vec![0x84, 1, 0x60, 0x2E]; 3]);
+        assert_eq!(maestro.home_channel(1, 181), Err(MaestroError::OutOfBounds));
+        assert_eq!(maestro.home_channel(12, 90), Err(MaestroError::InvalidChannel));
+        assert_eq!(maestro.set_channel_home(12, 90.0), Err(MaestroError::InvalidChannel));
+    }
+
+    #[test]
+    fn set_uniform_speed_and_acceleration_batch_commands() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_uniform_speed(&[0, 1], 200).unwrap();
+        maestro.set_uniform_acceleration(&[0, 1], 10).unwrap();
+        assert_eq!(transport.writes(), vec![
+            vec![0x87, 0, 0x48, 1, 0x87, 1, 0x48, 1],
+            vec![0x89, 0, 10, 0, 0x89, 1, 10, 0],
+        ]);
+        assert_eq!(maestro.set_uniform_speed(&[0, 12], 200), Err(MaestroError::InvalidChannel));
+        assert_eq!(transport.writes().len(), 2);
+    }
+
+    #[test]
+    fn emergency_stop_disables_every_channel_ignoring_limits() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_channel_limits(0, 45.0, 135.0).unwrap();
+        maestro.emergency_stop().unwrap();
+        let expected: Vec<u8> = (0..12).flat_map(|channel| [0x84, channel, 0, 0]).collect();
+        assert_eq!(transport.writes(), vec![expected]);
+    }
+
+    #[test]
+    fn simulated_position_ramps_toward_target() {
+        let mut maestro = Maestro::simulated();
+        maestro.set_target(0, 6000).unwrap();
+        assert_eq!(maestro.get_position_raw(0).unwrap(), 6000);
+        maestro.set_speed_raw(0, 1).unwrap();
+        maestro.set_target(0, 8000).unwrap();
+        thread::sleep(Duration::from_millis(30));
+        let position = maestro.get_position_raw(0).unwrap();
+        assert!(position > 6000 && position < 8000);
+        assert_eq!(maestro.get_moving_state().unwrap(), MovingState::ServosMoving);
+        maestro.set_speed(0, Speed::UNLIMITED).unwrap();
+        assert_eq!(maestro.get_pos_motors(vec![0]).unwrap(), vec![quarter_us_to_degrees(8000)]);
+        assert_eq!(maestro.get_moving_state().unwrap(), MovingState::ServosStopped);
+    }
+
+    #[test]
+    fn read_at_end_of_file_reports_disconnected() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.set_read_error(Some(ErrorKind::UnexpectedEof));
+        assert_eq!(maestro.get_position_raw(0), Err(MaestroError::Disconnected));
+        transport.set_read_error(None);
+        maestro.set_timeout(Duration::from_millis(5)).unwrap();
+        assert_eq!(maestro.get_position_raw(0), Err(MaestroError::UnableToReceive));
+    }
+
+    #[test]
+    fn device_info_matches_usb_port() {
+        let maestro = Maestro::from_transport(MemoryTransport::new());
+        assert_eq!(maestro.device_info(), None);
+        let usb = serialport::UsbPortInfo {
+            vid: 0x1FFB,
+            pid: 0x008A,
+            serial_number: Some("00012345".to_string()),
+            manufacturer: Some("Pololu Corporation".to_string()),
+            product: Some("Pololu Mini Maestro 12-Channel USB Servo Controller".to_string()),
+        };
+        let ports = vec![
+            SerialPortInfo { port_name: "COM1".to_string(), port_type: SerialPortType::PciPort },
+            SerialPortInfo { port_name: "COM3".to_string(), port_type: SerialPortType::UsbPort(usb) },
+        ];
+        let info = find_device_info("COM3", ports.clone()).unwrap();
+        assert_eq!((info.vid, info.pid), (0x1FFB, 0x008A));
+        assert_eq!(info.serial_number.as_deref(), Some("00012345"));
+        assert_eq!(find_device_info("COM1", ports.clone()), None);
+        assert_eq!(find_device_info("COM9", ports), None);
+    }
+
+    #[test]
+    fn set_speed_and_acceleration_send_raw_values() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_speed(0, Speed::from_us_per_second(500.0).unwrap()).unwrap();
+        maestro.set_acceleration(0, Acceleration::from_raw(4)).unwrap();
+        assert_eq!(transport.writes(), vec![vec![0x87, 0, 20, 0], vec![0x89, 0, 4, 0]]);
+    }
+
+    #[test]
+    fn get_position_robust_returns_median() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[0x70, 0x17, 0xFF, 0x7F, 0x71, 0x17]);
+        assert_eq!(maestro.get_position_robust(2, 3).unwrap(), 6001);
+        assert_eq!(transport.writes(), vec![vec![0x90, 2, 0x90, 2, 0x90, 2]]);
+        assert_eq!(maestro.get_position_robust(2, 0), Err(MaestroError::OutOfBounds));
+    }
+
+    #[test]
+    fn home_and_verify_checks_positions() {
+        let mut maestro = Maestro::simulated();
+        maestro.set_channel_home(0, 90.0).unwrap();
+        maestro.set_channel_home(1, 45.0).unwrap();
+        maestro.home_and_verify(&[0, 1], 0.5, Duration::from_millis(100)).unwrap();
+        assert_eq!(maestro.get_position_raw(1).unwrap(), degrees_to_quarter_us(45.0).unwrap());
+        assert_eq!(maestro.home_and_verify(&[2], 0.5, Duration::from_millis(100)), Err(MaestroError::HomeNotSet));
+
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_channel_home(3, 90.0).unwrap();
+        transport.queue_response(&[0x00, 0x38, 0x18]);
+        assert_eq!(maestro.home_and_verify(&[3], 0.5, Duration::from_millis(100)), Err(MaestroError::HomeVerificationFailed(3)));
+        assert_eq!(transport.written(), vec![0x84, 3, 0x60, 0x2E, 0x93, 0x90, 3]);
+    }
+
+    #[test]
+    fn simulated_understands_crc_and_device_numbers() {
+        let mut maestro = Maestro::simulated();
+        maestro.enable_crc(true);
+        maestro.set_target(0, 6000).unwrap();
+        maestro.set_multiple_targets(1, &[7000, 5000]).unwrap();
+        assert_eq!(maestro.get_all_positions().unwrap()[..3], [6000, 7000, 5000]);
+        maestro.device_number = Some(12);
+        maestro.set_target(0, 4000).unwrap();
+        assert_eq!(maestro.get_position_raw(0).unwrap(), 4000);
+        assert_eq!(maestro.get_moving_state().unwrap(), MovingState::ServosStopped);
+    }
+
+    #[test]
+    fn get_all_positions_reads_every_channel() {
+        let mut maestro = Maestro::simulated();
+        maestro.set_target(0, 6000).unwrap();
+        maestro.set_target(11, 7000).unwrap();
+        let positions = maestro.get_all_positions().unwrap();
+        assert_eq!(positions.len(), 12);
+        assert_eq!((positions[0], positions[1], positions[11]), (6000, 0, 7000));
+    }
+
+    #[test]
+    fn values_wider_than_14_bits_are_rejected() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert_eq!(split_data(0x3FFF).unwrap(), [0x7F, 0x7F]);
+        assert_eq!(maestro.set_target(0, 0x4000), Err(MaestroError::OutOfBounds));
+        assert_eq!(maestro.set_multiple_targets(0, &[6000, 0x4000]), Err(MaestroError::OutOfBounds));
+        assert_eq!(maestro.set_pwm(0x4000, 100), Err(MaestroError::OutOfBounds));
+        assert_eq!(maestro.restart_script_at_with_param(0, u16::MAX), Err(MaestroError::OutOfBounds));
+        assert!(transport.writes().is_empty());
+    }
+
+    #[test]
+    fn move_group_sets_speeds_before_targets() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.move_group(&[(3, 0.0, 10), (2, 90.0, 20)]).unwrap();
+        maestro.move_group(&[(1, 0.0, 10), (5, 90.0, 20)]).unwrap();
+        assert_eq!(transport.writes(), vec![
+            vec![0x87, 3, 10, 0, 0x87, 2, 20, 0, 0x9F, 2, 2, 0x60, 0x2E, 0x40, 0x0F],
+            vec![0x87, 1, 10, 0, 0x87, 5, 20, 0, 0x84, 1, 0x40, 0x0F, 0x84, 5, 0x60, 0x2E],
+        ]);
+        assert_eq!(maestro.move_group(&[(1, 0.0, 10), (12, 90.0, 20)]), Err(MaestroError::InvalidChannel));
+        assert_eq!(transport.writes().len(), 2);
+    }
+
+    #[test]
+    fn sweep_records_each_step() {
+        let mut maestro = Maestro::simulated();
+        let readings = maestro.sweep(0, 100.0, 90.0, 4.0, Duration::ZERO).unwrap();
+        let angles: Vec<f64> = readings.iter().map(|&(degree, _)| degree).collect();
+        assert_eq!(angles, vec![100.0, 96.0, 92.0, 90.0]);
+        assert_eq!(readings[3].1, degrees_to_quarter_us(90.0).unwrap());
+        assert_eq!(maestro.sweep(0, 0.0, 90.0, 0.0, Duration::ZERO), Err(MaestroError::OutOfBounds));
+        maestro.set_channel_limits(0, 95.0, 180.0).unwrap();
+        assert_eq!(maestro.sweep(0, 100.0, 90.0, 4.0, Duration::ZERO), Err(MaestroError::LimitExceeded));
+    }
+
+    #[test]
+    fn command_queue_writes_held_commands_in_background() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert_eq!(maestro.set_command_queue(Some(0)), Err(MaestroError::OutOfBounds));
+        maestro.set_command_queue(Some(100)).unwrap();
+        maestro.set_target(0, 6000).unwrap();
+        maestro.set_target(1, 6000).unwrap();
+        maestro.set_target(2, 6000).unwrap();
+        // Nothing else is called, so the writer thread alone has to drain what was held.
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(transport.written(), [0x84, 0, 0x70, 0x2E, 0x84, 1, 0x70, 0x2E, 0x84, 2, 0x70, 0x2E]);
+        assert!(transport.writes().len() <= 2);
+    }
+
+    #[test]
+    fn command_queue_writes_held_commands_before_reads() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_command_queue(Some(100)).unwrap();
+        maestro.set_target(0, 6000).unwrap();
+        maestro.set_target(1, 6000).unwrap();
+        transport.queue_response(&[0x70, 0x17].repeat(12));
+        assert_eq!(maestro.get_all_positions().unwrap(), vec![6000; 12]);
+        let written = transport.written();
+        assert_eq!(written[..8], [0x84, 0, 0x70, 0x2E, 0x84, 1, 0x70, 0x2E]);
+        assert_eq!(written[8..12], [0x90, 0, 0x90, 1]);
+        maestro.set_target(3, 6000).unwrap();
+        maestro.emergency_stop().unwrap();
+        maestro.flush().unwrap();
+        assert_eq!(transport.writes().last().unwrap()[..4], [0x84, 0, 0, 0]);
+    }
+
+    #[test]
+    fn pipelined_reads_work_with_command_queue() {
+        let mut maestro = Maestro::simulated();
+        maestro.set_command_queue(Some(960)).unwrap();
+        maestro.set_target(0, 6000).unwrap();
+        maestro.set_target(1, 7000).unwrap();
+        assert_eq!(maestro.get_all_positions().unwrap()[..2], [6000, 7000]);
+    }
+
+    #[test]
+    fn response_timeout_does_not_reconnect() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_connection(Connection { port: "COM0".to_string(), baud: 9600 });
+        maestro.set_auto_reconnect(3);
+        maestro.set_timeout(Duration::from_millis(5)).unwrap();
+        assert_eq!(maestro.get_position_raw(0), Err(MaestroError::UnableToReceive));
+        maestro.set_target(0, 6000).unwrap();
+        assert_eq!(transport.writes().len(), 2);
+    }
+
+    #[test]
+    fn pipelined_read_is_one_exchange() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_timeout(Duration::from_millis(5)).unwrap();
+        transport.set_max_read(1);
+        transport.queue_response(&[0x70, 0x17, 0x70]);
+        assert_eq!(maestro.get_all_positions(), Err(MaestroError::UnableToReceive));
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn move_group_rejects_duplicate_channels() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert_eq!(maestro.move_group(&[(1, 90.0, 0), (2, 45.0, 0), (1, 0.0, 0)]), Err(MaestroError::InvalidChannel));
+        assert!(transport.writes().is_empty());
+    }
+
+    /// Transport that answers every read with one zero byte, at most once per `interval`.
+    struct TrickleTransport {
+        interval: Duration,
+        next_byte_at: Instant,
+        timeout: Duration
+    }
+
+    impl TrickleTransport {
+        fn new(interval: Duration) -> Self {
+            TrickleTransport { interval, next_byte_at: Instant::now() + interval, timeout: Duration::ZERO }
+        }
+    }
+
+    impl Read for TrickleTransport {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if Instant::now() < self.next_byte_at {
+                thread::sleep(Duration::from_millis(1));
+                return Err(ErrorKind::TimedOut.into());
+            }
+            self.next_byte_at = Instant::now() + self.interval;
+            buf[0] = 0;
+            Ok(1)
+        }
+    }
+
+    impl Write for TrickleTransport {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl MaestroTransport for TrickleTransport {
+        fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        fn set_timeout(&mut self, timeout: Duration) -> std::io::Result<()> {
+            self.timeout = timeout;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn slow_response_fails_at_total_deadline() {
+        let mut maestro = Maestro::from_transport(TrickleTransport::new(Duration::from_millis(30)));
+        maestro.set_timeout(Duration::from_millis(50)).unwrap();
+        assert_eq!(maestro.get_position_raw(0), Err(MaestroError::UnableToReceive));
+    }
+
+    #[test]
+    fn pipelined_read_deadline_scales_with_channels() {
+        let mut maestro = Maestro::from_transport(TrickleTransport::new(Duration::from_millis(3)));
+        maestro.set_timeout(Duration::from_millis(20)).unwrap();
+        assert_eq!(maestro.get_all_positions().unwrap(), vec![0; 12]);
+    }
+
+    #[test]
+    fn sweep_steps_stay_in_range_despite_rounding() {
+        let mut maestro = Maestro::simulated();
+        let readings = maestro.sweep(0, 179.2, 0.0, 2.56, Duration::ZERO).unwrap();
+        assert_eq!(readings.len(), 71);
+        assert_eq!(readings.last().unwrap().0, 0.0);
+        let readings = maestro.sweep(0, 0.0, 174.2, 1.3, Duration::ZERO).unwrap();
+        let angles: Vec<f64> = readings.iter().map(|&(degree, _)| degree).collect();
+        assert_eq!(angles.len(), 135);
+        assert_eq!(angles[134], 174.2);
+        assert!(angles[133] < 173.0);
+    }
+
+    /// Transport whose writes panic, standing in for a bug in a custom transport.
+    struct PanickingTransport;
+
+    impl Read for PanickingTransport {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(ErrorKind::TimedOut.into())
+        }
+    }
+
+    impl Write for PanickingTransport {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            panic!("transport failed");
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl MaestroTransport for PanickingTransport {
+        fn timeout(&self) -> Duration {
+            Duration::ZERO
+        }
+
+        fn set_timeout(&mut self, _timeout: Duration) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn command_queue_reports_stopped_writer() {
+        let mut maestro = Maestro::from_transport(PanickingTransport);
+        maestro.set_command_queue(Some(1000)).unwrap();
+        maestro.set_target(0, 6000).unwrap();
+        assert_eq!(maestro.flush(), Err(MaestroError::UnableToSend));
+        assert_eq!(maestro.set_target(0, 6000), Err(MaestroError::UnableToSend));
+    }
+
+    #[test]
+    fn get_errors_decodes_flags() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        transport.queue_response(&[0x09, 0x01]);
+        let flags = maestro.get_errors().unwrap();
+        assert_eq!(transport.written(), vec![0xA1]);
+        assert_eq!(flags.raw(), 0x0109);
+        assert!(flags.serial_signal_error());
+        assert!(!flags.serial_overrun());
+        assert!(flags.serial_crc_error());
+        assert!(flags.script_program_counter_error());
+    }
+
+    #[test]
+    fn speed_and_acceleration_bounds() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert!(maestro.set_speed_raw(0, SPEED_MAX).is_ok());
+        assert!(matches!(maestro.set_speed_raw(0, SPEED_MAX + 1), Err(MaestroError::OutOfBounds)));
+        assert!(maestro.set_acceleration_raw(0, ACCEL_MAX).is_ok());
+        assert_eq!(transport.writes()[1], vec![0x89, 0, 0x7F, 0x01]);
+        assert_eq!(transport.writes().len(), 2);
+    }
+
+    #[test]
+    fn set_multiple_targets_single_write() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        maestro.set_multiple_targets(3, &[6000, 7000]).unwrap();
+        assert_eq!(transport.writes(), vec![vec![0x9F, 2, 3, 0x70, 0x2E, 0x58, 0x36]]);
+    }
+
+    #[test]
+    fn set_multiple_targets_rejects_overflowing_range() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert!(matches!(maestro.set_multiple_targets(10, &[6000, 6000, 6000]), Err(MaestroError::InvalidChannel)));
+        assert!(maestro.set_multiple_targets(10, &[6000, 6000]).is_ok());
+        assert_eq!(transport.writes().len(), 1);
+    }
+
+    #[test]
+    fn set_multiple_targets_with_no_targets_sends_nothing() {
+        let transport = MemoryTransport::new();
+        let mut maestro = Maestro::from_transport(transport.clone());
+        assert!(maestro.set_multiple_targets(3, &[]).is_ok());
+        assert!(matches!(maestro.set_multiple_targets(12, &[]), Err(MaestroError::InvalidChannel)));
+        assert!(transport.writes().is_empty());
+    }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
     #[test]
+    #[ignore = "requires a Maestro connected on COM1"]
     fn hardware_test() {
-        let mut maestro = Maestro::new("COM1");
-        let micro_seconds: u16 = convert_deg_to_quarter_micros(15.0).unwrap();
-        let data = form_data(0x84, 0x00, micro_seconds);
+        let maestro = Maestro::new("COM1");
+        let micro_seconds: u16 = degrees_to_quarter_us(15.0).unwrap();
+        let data = form_data(0x84, 0x00, micro_seconds).unwrap();
         maestro.unwrap().send_command_no_response(&data).unwrap();
     }
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+//! Encoders for the Maestro's serial protocol.
+//!
+//! These are pure functions with no dependency on `std` or a serial port, so they are available with
+//! `default-features = false` for embedded hosts that drive a Maestro over their own UART.
+
+/// Largest value that fits in the two 7-bit data bytes of a command.
+pub const DATA_MAX: u16 = 0x3FFF;
+
+/// First byte of every Pololu protocol command.
+pub const POLOLU_START_BYTE: u8 = 0xAA;
+
+/// Generator polynomial for the Maestro's CRC-7, bit-reversed for LSB-first processing.
+const CRC7_POLY: u8 = 0x91;
+
+/// Splits a value into the low and high 7-bit data bytes used by the protocol.
+///
+/// Returns `None` for values that do not fit in 14 bits rather than truncating them.
+///
+/// # Example:
+/// ```
+/// use maestro_control::protocol::split_data;
+///
+/// assert_eq!(split_data(6000), Some([0x70, 0x2E]));
+/// assert_eq!(split_data(0x4000), None);
+/// ```
+pub fn split_data(data: u16) -> Option<[u8; 2]> {
+    if data > DATA_MAX { return None }
+    Some([(data & 0x7F) as u8, (data >> 7) as u8])
+}
+
+/// Encodes a Compact protocol command that takes a channel and a 14-bit value, such as Set Target
+/// (`0x84`), Set Speed (`0x87`) or Set Acceleration (`0x89`).
+///
+/// Returns `None` if `data` does not fit in 14 bits.
+pub fn form_data(command: u8, channel: u8, data: u16) -> Option<[u8; 4]> {
+    let [low, high] = split_data(data)?;
+    Some([command, channel, low, high])
+}
+
+/// The bytes that replace a Compact protocol command byte when addressing a board with the Pololu
+/// protocol: the start byte, the device number, and the command with its most significant bit cleared.
+///
+/// The rest of the command follows unchanged.
+pub fn pololu_header(device_number: u8, command: u8) -> [u8; 3] {
+    [POLOLU_START_BYTE, device_number, command & 0x7F]
+}
+
+/// Computes the CRC-7 byte the Maestro expects after a command when CRC is enabled.
+pub fn crc7(message: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for byte in message {
+        crc ^= byte;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc ^= CRC7_POLY;
+            }
+            crc >>= 1;
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc7_matches_pololu_example() {
+        assert_eq!(crc7(&[0x83, 0x01]), 0x17);
+    }
+
+    #[test]
+    fn form_data_rejects_values_wider_than_14_bits() {
+        assert_eq!(form_data(0x84, 0, DATA_MAX), Some([0x84, 0, 0x7F, 0x7F]));
+        assert_eq!(form_data(0x84, 0, DATA_MAX + 1), None);
+    }
+
+    #[test]
+    fn pololu_header_clears_command_msb() {
+        assert_eq!(pololu_header(12, 0x84), [0xAA, 12, 0x04]);
+    }
+}
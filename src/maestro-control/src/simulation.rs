@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+use crate::builder::MAX_CHANNEL_COUNT;
+use crate::protocol::POLOLU_START_BYTE;
+use crate::transport::MaestroTransport;
+
+/// Time the Maestro's speed limit is expressed over.
+const SPEED_PERIOD: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SimulatedChannel {
+    target: u16,
+    position: f64,
+    speed: u16
+}
+
+/// Transport that behaves like a Maestro instead of talking to one.
+///
+/// Compact protocol commands update per-channel targets and speeds. Positions move toward their
+/// targets at the configured speed in real time, and Get Position, Get Moving State, Get Errors and
+/// Get Script Status are answered from that state. Accelerations, PWM, scripts and the board's home
+/// positions are accepted but not simulated. Pololu protocol commands are answered whatever their
+/// device number, and CRC bytes are skipped without being checked.
+pub(crate) struct SimulatedTransport {
+    channels: [SimulatedChannel; MAX_CHANNEL_COUNT as usize],
+    pending: Vec<u8>,
+    responses: VecDeque<u8>,
+    last_update: Instant,
+    timeout: Duration
+}
+
+impl SimulatedTransport {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        SimulatedTransport {
+            channels: [SimulatedChannel::default(); MAX_CHANNEL_COUNT as usize],
+            pending: Vec::new(),
+            responses: VecDeque::new(),
+            last_update: Instant::now(),
+            timeout
+        }
+    }
+
+    /// Moves every channel toward its target by the time elapsed since the last update.
+    fn advance(&mut self) {
+        let now = Instant::now();
+        let periods = (now - self.last_update).as_secs_f64() / SPEED_PERIOD.as_secs_f64();
+        self.last_update = now;
+        for channel in &mut self.channels {
+            let target = channel.target as f64;
+            if channel.speed == 0 {
+                channel.position = target;
+                continue;
+            }
+            let step = channel.speed as f64 * periods;
+            channel.position = if channel.position < target {
+                (channel.position + step).min(target)
+            } else {
+                (channel.position - step).max(target)
+            };
+        }
+    }
+
+    fn set_target(&mut self, channel: u8, target: u16) {
+        let Some(channel) = self.channels.get_mut(channel as usize) else { return };
+        channel.target = target;
+        // Outputs that were off, or are being turned off, jump straight to the new target.
+        if channel.position == 0.0 || target == 0 {
+            channel.position = target as f64;
+        }
+    }
+
+    /// Runs the first complete command in `pending`, returning how many bytes it used.
+    ///
+    /// Returns `None` if the command is not complete yet.
+    fn execute(&mut self) -> Option<usize> {
+        let pending = &self.pending;
+        // Only data bytes are below 0x80, so one where a command should start is a CRC byte.
+        if pending[0] < 0x80 { return Some(1) }
+        // The Pololu protocol puts the start byte and device number ahead of the command byte, whose
+        // most significant bit is cleared.
+        let (prefix, opcode) = match pending[0] {
+            POLOLU_START_BYTE => (2, *pending.get(2)? | 0x80),
+            opcode => (0, opcode)
+        };
+        let body = &pending[prefix..];
+        let len = match opcode {
+            0x84 | 0x87 | 0x89 | 0xA8 => 4,
+            0x90 | 0xA7 => 2,
+            0x8A => 5,
+            0x9F => 3 + 2 * *body.get(1)? as usize,
+            0xFF => 3,
+            _ => 1
+        };
+        if body.len() < len { return None }
+        let mut command: Vec<u8> = body[..len].to_vec();
+        command[0] = opcode;
+        let value = |i: usize| command[i] as u16 | (command[i + 1] as u16) << 7;
+        match command[0] {
+            0x84 => self.set_target(command[1], value(2)),
+            0x87 => if let Some(channel) = self.channels.get_mut(command[1] as usize) { channel.speed = value(2) },
+            0x90 => {
+                let position = self.channels.get(command[1] as usize).map_or(0, |c| c.position.round() as u16);
+                self.responses.extend(position.to_le_bytes());
+            }
+            0x93 => {
+                let moving = self.channels.iter().any(|c| c.position != c.target as f64);
+                self.responses.push_back(moving as u8);
+            }
+            0x9F => {
+                for i in 0..command[1] {
+                    self.set_target(command[2].wrapping_add(i), value(3 + 2 * i as usize));
+                }
+            }
+            0xA1 => self.responses.extend([0, 0]),
+            0xAE => self.responses.push_back(1),
+            _ => {}
+        }
+        Some(prefix + len)
+    }
+}
+
+impl Read for SimulatedTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.responses.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no pending response"));
+        }
+        let mut count = 0;
+        while count < buf.len() {
+            match self.responses.pop_front() {
+                Some(byte) => buf[count] = byte,
+                None => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for SimulatedTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.advance();
+        self.pending.extend_from_slice(buf);
+        while !self.pending.is_empty() {
+            match self.execute() {
+                Some(len) => { self.pending.drain(..len); }
+                None => break
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl MaestroTransport for SimulatedTransport {
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serialport::SerialPort;
+
+/// Byte stream a `Maestro` sends commands over.
+///
+/// Implemented for serial ports and for [`MemoryTransport`]. Implement it to drive a Maestro over
+/// any other link.
+pub trait MaestroTransport: Read + Write + Send {
+    /// How long a read waits for data before failing with `ErrorKind::TimedOut`.
+    fn timeout(&self) -> Duration;
+
+    /// Sets how long a read waits for data before failing with `ErrorKind::TimedOut`.
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()>;
+}
+
+impl MaestroTransport for Box<dyn SerialPort> {
+    fn timeout(&self) -> Duration {
+        self.as_ref().timeout()
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.as_mut().set_timeout(timeout).map_err(io::Error::from)
+    }
+}
+
+/// State shared between clones of a `MemoryTransport`.
+#[derive(Debug, Default)]
+struct MemoryState {
+    writes: Vec<Vec<u8>>,
+    responses: VecDeque<u8>,
+    max_read: Option<usize>,
+    write_error: Option<io::ErrorKind>,
+    read_error: Option<io::ErrorKind>,
+    timeout: Duration,
+}
+
+/// In-memory transport that records written bytes and replays queued responses.
+///
+/// Clones share the same state, so a clone can be kept to inspect what a `Maestro` sent.
+///
+/// # Example:
+/// ```
+/// use maestro_control::{Maestro, MemoryTransport};
+///
+/// let transport = MemoryTransport::new();
+/// let mut maestro = Maestro::from_transport(transport.clone());
+/// transport.queue_response(&[0x70, 0x17]);
+/// assert_eq!(maestro.get_position_raw(0).unwrap(), 6000);
+/// assert_eq!(transport.written(), vec![0x90, 0]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTransport {
+    state: Arc<Mutex<MemoryState>>,
+}
+
+impl MemoryTransport {
+    /// Creates a transport with nothing written and no queued responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues bytes to be returned by subsequent reads.
+    pub fn queue_response(&self, bytes: &[u8]) {
+        self.state.lock().unwrap().responses.extend(bytes);
+    }
+
+    /// Limits how many bytes a single read returns, to simulate responses arriving in pieces.
+    pub fn set_max_read(&self, max_read: usize) {
+        self.state.lock().unwrap().max_read = Some(max_read);
+    }
+
+    /// Makes every following write fail with the given error kind, or succeed again with `None`.
+    ///
+    /// Failed writes are still recorded.
+    pub fn set_write_error(&self, kind: Option<io::ErrorKind>) {
+        self.state.lock().unwrap().write_error = kind;
+    }
+
+    /// Makes every following read fail with the given error kind, or behave normally again with `None`.
+    pub fn set_read_error(&self, kind: Option<io::ErrorKind>) {
+        self.state.lock().unwrap().read_error = kind;
+    }
+
+    /// Every individual write made against the transport.
+    pub fn writes(&self) -> Vec<Vec<u8>> {
+        self.state.lock().unwrap().writes.clone()
+    }
+
+    /// All written bytes concatenated in order.
+    pub fn written(&self) -> Vec<u8> {
+        self.state.lock().unwrap().writes.concat()
+    }
+}
+
+impl Read for MemoryTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(kind) = state.read_error {
+            return Err(io::Error::new(kind, "simulated read failure"));
+        }
+        if state.responses.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "no queued response"));
+        }
+        let len = buf.len().min(state.max_read.unwrap_or(usize::MAX));
+        let mut count = 0;
+        while count < len {
+            match state.responses.pop_front() {
+                Some(byte) => buf[count] = byte,
+                None => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for MemoryTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        state.writes.push(buf.to_vec());
+        match state.write_error {
+            Some(kind) => Err(io::Error::new(kind, "simulated write failure")),
+            None => Ok(buf.len())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl MaestroTransport for MemoryTransport {
+    fn timeout(&self) -> Duration {
+        self.state.lock().unwrap().timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.state.lock().unwrap().timeout = timeout;
+        Ok(())
+    }
+}
+
+/// Placeholder transport for a port that has been closed, failing every read and write.
+pub(crate) struct ClosedTransport {
+    timeout: Duration,
+}
+
+impl ClosedTransport {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        ClosedTransport { timeout }
+    }
+}
+
+impl Read for ClosedTransport {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::from(io::ErrorKind::NotConnected))
+    }
+}
+
+impl Write for ClosedTransport {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::from(io::ErrorKind::NotConnected))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::NotConnected))
+    }
+}
+
+impl MaestroTransport for ClosedTransport {
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use crate::error::MaestroError;
+use crate::maestro::{ACCEL_MAX, SPEED_MAX};
+
+/// Raw speed units per µs/s: the Maestro counts quarter-microseconds per 10ms.
+const SPEED_PER_US_PER_SECOND: f64 = 4.0 / 100.0;
+/// Raw acceleration units per µs/s²: the Maestro counts quarter-microseconds per 10ms per 80ms.
+const ACCEL_PER_US_PER_SECOND_SQUARED: f64 = 4.0 / 100.0 / 12.5;
+
+/// A speed limit for `Maestro::set_speed`.
+///
+/// Stores the raw Maestro value, in units of (0.25µs)/(10ms). A speed of 0 means no limit.
+///
+/// # Example:
+/// ```
+/// use maestro_control::Speed;
+///
+/// // 500µs of pulse width per second
+/// assert_eq!(Speed::from_us_per_second(500.0).unwrap().raw(), 20);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Speed(u16);
+
+impl Speed {
+    /// No speed limit.
+    pub const UNLIMITED: Speed = Speed(0);
+
+    /// Creates a speed from a raw Maestro value.
+    /// # Errors:
+    /// - `OutOfBounds` if raw is greater than `SPEED_MAX`
+    pub fn from_raw(raw: u16) -> Result<Self, MaestroError> {
+        if raw > SPEED_MAX { return Err(MaestroError::OutOfBounds) }
+        Ok(Speed(raw))
+    }
+
+    /// Creates a speed from the rate the pulse width changes, in microseconds per second.
+    ///
+    /// The value is rounded to the nearest raw unit (25µs/s). Nonzero rates below half a unit are
+    /// rounded up to one unit rather than down to 0, which would remove the limit.
+    /// # Errors:
+    /// - `OutOfBounds` if the rate is negative, not finite, or larger than `SPEED_MAX` allows
+    pub fn from_us_per_second(us_per_second: f64) -> Result<Self, MaestroError> {
+        to_raw(us_per_second * SPEED_PER_US_PER_SECOND, SPEED_MAX).map(Speed)
+    }
+
+    /// The raw Maestro value.
+    pub fn raw(self) -> u16 {
+        self.0
+    }
+
+    /// The rate the pulse width changes, in microseconds per second.
+    pub fn us_per_second(self) -> f64 {
+        self.0 as f64 / SPEED_PER_US_PER_SECOND
+    }
+}
+
+/// An acceleration limit for `Maestro::set_acceleration`.
+///
+/// Stores the raw Maestro value, in units of (0.25µs)/(10ms)/(80ms). An acceleration of 0 means no limit.
+///
+/// # Example:
+/// ```
+/// use maestro_control::Acceleration;
+///
+/// assert_eq!(Acceleration::from_us_per_second_squared(3125.0).unwrap().raw(), 10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Acceleration(u8);
+
+impl Acceleration {
+    /// No acceleration limit.
+    pub const UNLIMITED: Acceleration = Acceleration(0);
+
+    /// Creates an acceleration from a raw Maestro value.
+    pub fn from_raw(raw: u8) -> Self {
+        Acceleration(raw)
+    }
+
+    /// Creates an acceleration from the rate the speed changes, in microseconds per second squared.
+    ///
+    /// The value is rounded to the nearest raw unit (312.5µs/s²). Nonzero rates below half a unit
+    /// are rounded up to one unit rather than down to 0, which would remove the limit.
+    /// # Errors:
+    /// - `OutOfBounds` if the rate is negative, not finite, or larger than `ACCEL_MAX` allows
+    pub fn from_us_per_second_squared(us_per_second_squared: f64) -> Result<Self, MaestroError> {
+        let raw = to_raw(us_per_second_squared * ACCEL_PER_US_PER_SECOND_SQUARED, ACCEL_MAX.into())?;
+        Ok(Acceleration(raw as u8))
+    }
+
+    /// The raw Maestro value.
+    pub fn raw(self) -> u8 {
+        self.0
+    }
+
+    /// The rate the speed changes, in microseconds per second squared.
+    pub fn us_per_second_squared(self) -> f64 {
+        self.0 as f64 / ACCEL_PER_US_PER_SECOND_SQUARED
+    }
+}
+
+fn to_raw(value: f64, max: u16) -> Result<u16, MaestroError> {
+    if !value.is_finite() || value < 0.0 { return Err(MaestroError::OutOfBounds) }
+    let raw = value.round();
+    if raw > max as f64 { return Err(MaestroError::OutOfBounds) }
+    Ok(if raw == 0.0 && value > 0.0 { 1 } else { raw as u16 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_converts_units() {
+        assert_eq!(Speed::from_us_per_second(0.0).unwrap(), Speed::UNLIMITED);
+        assert_eq!(Speed::from_us_per_second(1.0).unwrap().raw(), 1);
+        assert_eq!(Speed::from_us_per_second(2500.0).unwrap().us_per_second(), 2500.0);
+        assert_eq!(Speed::from_us_per_second(-1.0), Err(MaestroError::OutOfBounds));
+        assert_eq!(Speed::from_raw(SPEED_MAX + 1), Err(MaestroError::OutOfBounds));
+    }
+
+    #[test]
+    fn acceleration_converts_units() {
+        assert_eq!(Acceleration::from_us_per_second_squared(312.5).unwrap().raw(), 1);
+        assert_eq!(Acceleration::from_us_per_second_squared(f64::NAN), Err(MaestroError::OutOfBounds));
+        assert_eq!(Acceleration::from_us_per_second_squared(80000.0), Err(MaestroError::OutOfBounds));
+    }
+}